@@ -1,123 +1,358 @@
-use crate::sim::SimProcess;
+use crate::events::EventSink;
+use crate::sim::{self, ProcessId, Scheduler, SimProcess};
 use crate::structures::DLL;
-pub fn fcfs(mut incoming: Vec<SimProcess>) -> Vec<SimProcess> {
-    let mut finished: Vec<SimProcess> = vec![];
-    incoming.reverse();
-    let mut running_time = 0;
-    while incoming.len() != 0 {
-        let mut process_current = incoming.pop().unwrap();
-        let burst_time = process_current.remaining_burst;
-        process_current.run_burst(running_time, burst_time);
-        finished.push(process_current);
-        running_time += burst_time;
-    }
-    finished
-}
-
-fn mergesort(collection: Vec<SimProcess>) -> Vec<SimProcess> {
-    let length = collection.len();
-    if length == 0 {
-        return collection;
-    } else {
-        let mut collection: Vec<Option<SimProcess>> =
-            collection.into_iter().map(|x| Some(x)).collect();
-        thunk_mergesort(&mut collection[..], length)
-            .into_iter()
-            .map(|x| x.unwrap())
-            .collect::<Vec<SimProcess>>()
-    }
-}
-
-fn thunk_mergesort(
-    collection: &mut [Option<SimProcess>],
-    length: usize,
-) -> Vec<Option<SimProcess>> {
-    let mut result = Vec::with_capacity(length);
-    // do nothing, already sorted
-    if length == 1 {
-        result.push(collection[0].take());
-        result
-    } else {
-        let len_a = length / 2;
-        let len_b = length - len_a;
-        let (collection_a, collection_b) = collection.split_at_mut(len_a);
-        let mut collection_a = thunk_mergesort(collection_a, len_a);
-        let mut collection_b = thunk_mergesort(collection_b, len_b);
-
-        // merge
-        let mut index_a = 0;
-        let mut index_b = 0;
-
-        for _ in 0..length {
-            if index_a < len_a
-                && (index_b == len_b || &collection_a[index_a] <= &collection_b[index_b])
-            {
-                result.push(collection_a[index_a].take());
-                index_a += 1;
-            } else if index_b < len_b
-                && (index_a == len_a || &collection_b[index_b] <= &collection_a[index_a])
-            {
-                result.push(collection_b[index_b].take());
-                index_b += 1;
-            }
+
+/// the processes waiting for the cpu, in the order they will be served.
+#[derive(Default)]
+pub struct ReadyQueue {
+    ids: DLL<ProcessId>,
+}
+
+impl ReadyQueue {
+    pub fn push_back(&mut self, id: ProcessId) {
+        self.ids.append(id);
+    }
+
+    /// insert a process ahead of every process with a greater key. processes with equal keys are
+    /// served in the order they became ready.
+    pub fn insert_by<K: Ord>(
+        &mut self,
+        id: ProcessId,
+        processes: &[SimProcess],
+        key: impl Fn(&SimProcess) -> K,
+    ) {
+        let own_key = key(&processes[id]);
+        match self
+            .ids
+            .iter()
+            .position(|other| key(&processes[*other]) > own_key)
+        {
+            Some(index) => self.ids.insert(index, id),
+            None => self.ids.append(id),
         }
+    }
+
+    pub fn pop_front(&mut self) -> Option<ProcessId> {
+        self.ids.pop_front()
+    }
 
-        result
+    pub fn ids(&self) -> Vec<ProcessId> {
+        self.ids.iter().copied().collect()
     }
 }
 
-/// using the rules applied via the struct and mergesort implementation, fill algorithmic
-/// requirements by applying a simple sort and transitioning to the standard FCFS approach.
-pub fn sort_before_fcfs(incoming: Vec<SimProcess>) -> Vec<SimProcess> {
-    fcfs(mergesort(incoming))
+/// first-come, first-served: processes run to completion in the order they became ready.
+#[derive(Default)]
+pub struct Fcfs {
+    ready: ReadyQueue,
 }
 
-pub fn round_robin(incoming: Vec<SimProcess>, quantum: u32) -> Vec<SimProcess> {
-    let mut outgoing: DLL<SimProcess> = DLL::from(vec![]);
-    let mut incoming: DLL<SimProcess> = DLL::from(incoming);
-    let mut current_time: u32 = 0;
+impl Scheduler for Fcfs {
+    fn on_arrival(&mut self, id: ProcessId, _processes: &[SimProcess]) {
+        self.ready.push_back(id);
+    }
+
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        self.ready.pop_front()
+    }
+
+    fn ready(&self) -> Vec<ProcessId> {
+        self.ready.ids()
+    }
+}
+
+/// shortest-job-first: the ready process with the shortest next cpu burst runs to completion.
+#[derive(Default)]
+pub struct ShortestJobFirst {
+    ready: ReadyQueue,
+}
+
+impl Scheduler for ShortestJobFirst {
+    fn on_arrival(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.ready
+            .insert_by(id, processes, |process| process.remaining_burst);
+    }
+
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        self.ready.pop_front()
+    }
+
+    fn ready(&self) -> Vec<ProcessId> {
+        self.ready.ids()
+    }
+}
+
+/// priority scheduling: the ready process with the smallest priority value runs to completion.
+#[derive(Default)]
+pub struct PriorityFirst {
+    ready: ReadyQueue,
+}
+
+impl Scheduler for PriorityFirst {
+    fn on_arrival(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.ready
+            .insert_by(id, processes, |process| process.priority);
+    }
 
-    while !incoming.is_empty() {
-        if let Some(mut current_process) = incoming.pop_front() {
-            let mut burst = quantum;
-            let mut destination = Some(&mut incoming);
-            if current_process.remaining_burst > quantum {
-                current_process.run_burst(current_time, burst);
-            } else {
-                destination.replace(&mut outgoing);
-                burst -= burst - current_process.remaining_burst;
-                current_process.run_burst(current_time, burst);
-            }
-            current_time += burst;
-            println!("Time: {:06} | Burst Complete for {:06}", &current_time, &current_process);
-            destination.unwrap().append(current_process);
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        self.ready.pop_front()
+    }
+
+    fn ready(&self) -> Vec<ProcessId> {
+        self.ready.ids()
+    }
+}
+
+/// round-robin: ready processes take turns running for at most one quantum each.
+pub struct RoundRobin {
+    ready: ReadyQueue,
+    quantum: u32,
+}
+
+impl RoundRobin {
+    pub fn new(quantum: u32) -> Self {
+        Self {
+            ready: ReadyQueue::default(),
+            quantum,
         }
     }
-    outgoing.into()
 }
 
-pub fn priority_rr(incoming: Vec<SimProcess>, quantum: u32) -> Vec<SimProcess> {
-    let mut incoming = DLL::from(mergesort(incoming));
-    let mut outgoing: DLL<SimProcess> = DLL::new();
-    let mut current_time: u32 = 0;
+impl Scheduler for RoundRobin {
+    fn on_arrival(&mut self, id: ProcessId, _processes: &[SimProcess]) {
+        self.ready.push_back(id);
+    }
+
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        self.ready.pop_front()
+    }
 
-    while let Some(mut process) = incoming.pop_front() {
-        let burst = match quantum <= process.remaining_burst {
-            true => quantum,
-            false => quantum - (process.remaining_burst % quantum),
-        };
+    fn ready(&self) -> Vec<ProcessId> {
+        self.ready.ids()
+    }
+
+    fn quantum(&self) -> Option<u32> {
+        Some(self.quantum)
+    }
+}
 
-        process.run_burst(current_time, burst);
-        current_time += burst;
+/// priority with round-robin: processes run in priority order, taking turns one quantum at a
+/// time with other processes of the same priority.
+pub struct PriorityRoundRobin {
+    ready: ReadyQueue,
+    quantum: u32,
+}
 
-        if process.remaining_burst == 0 {
-            outgoing.append(process);
-        } else {
-            match incoming.iter().position(|x| x.priority > process.priority) {
-                Some(index) => incoming.insert(index, process),
-                None => incoming.append(process),
-            }
+impl PriorityRoundRobin {
+    pub fn new(quantum: u32) -> Self {
+        Self {
+            ready: ReadyQueue::default(),
+            quantum,
         }
     }
-    outgoing.into()
+}
+
+impl Scheduler for PriorityRoundRobin {
+    fn on_arrival(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.ready
+            .insert_by(id, processes, |process| process.priority);
+    }
+
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        self.ready.pop_front()
+    }
+
+    fn ready(&self) -> Vec<ProcessId> {
+        self.ready.ids()
+    }
+
+    fn quantum(&self) -> Option<u32> {
+        Some(self.quantum)
+    }
+}
+
+/// the weight of each nice value from -20 to 19, the same table linux uses. each step of nice
+/// changes a process' share of the cpu by about a quarter.
+const NICE_WEIGHTS: [u64; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+    3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+    70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+/// the weight of a process at nice 0.
+const NICE_0_WEIGHT: u64 = 1024;
+
+/// weighted fair sharing, after linux's completely fair scheduler. every process accrues virtual
+/// runtime, its cpu time scaled by the weight of the nice value its priority maps to, and the
+/// ready process with the least runs next for at most one quantum. a process becoming ready is
+/// moved up to the virtual runtime of the last process picked, so one that arrives late or comes
+/// back from i/o gets no more than its share. the virtual runtimes are saved with snapshots.
+pub struct WeightedFair {
+    /// the ready processes, in the order they became ready.
+    ready: Vec<ProcessId>,
+    quantum: u32,
+    /// the virtual runtime of each process, in 1024ths of a tick at nice 0.
+    vruntime: Vec<u64>,
+    /// the cpu time each process had been given when its virtual runtime was last updated.
+    served: Vec<u32>,
+    /// the virtual runtime of the last process picked, which never decreases.
+    min_vruntime: u64,
+}
+
+impl WeightedFair {
+    pub fn new(quantum: u32) -> Self {
+        Self {
+            ready: vec![],
+            quantum,
+            vruntime: vec![],
+            served: vec![],
+            min_vruntime: 0,
+        }
+    }
+
+    fn weight(process: &SimProcess) -> u64 {
+        let nice = crate::import::nice_of_priority(process.priority);
+        NICE_WEIGHTS[(nice + 20) as usize]
+    }
+
+    /// charge a process for the cpu time it has been given since it was last charged.
+    fn charge(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        if self.vruntime.len() < processes.len() {
+            self.vruntime.resize(processes.len(), 0);
+            self.served.resize(processes.len(), 0);
+        }
+        let process = &processes[id];
+        let served: u32 = process.history().iter().map(|burst| burst.length).sum();
+        let ran = (served - self.served[id]) as u64;
+        self.vruntime[id] += ran * NICE_0_WEIGHT * 1024 / Self::weight(process);
+        self.served[id] = served;
+    }
+
+    /// queue a process, no further behind than the last process picked.
+    fn enqueue(&mut self, id: ProcessId) {
+        self.vruntime[id] = self.vruntime[id].max(self.min_vruntime);
+        self.ready.push(id);
+    }
+
+    fn sorted(&self) -> Vec<ProcessId> {
+        let mut ids = self.ready.clone();
+        ids.sort_by_key(|id| self.vruntime[*id]);
+        ids
+    }
+}
+
+impl Scheduler for WeightedFair {
+    fn on_arrival(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.charge(id, processes);
+        self.enqueue(id);
+    }
+
+    fn on_quantum_expiry(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        // with several cpus another process may have been picked past this one while it ran.
+        self.charge(id, processes);
+        self.enqueue(id);
+    }
+
+    fn on_block(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.charge(id, processes);
+    }
+
+    fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+        let id = *self.sorted().first()?;
+        self.ready.retain(|other| *other != id);
+        self.min_vruntime = self.min_vruntime.max(self.vruntime[id]);
+        Some(id)
+    }
+
+    fn ready(&self) -> Vec<ProcessId> {
+        self.sorted()
+    }
+
+    fn quantum(&self) -> Option<u32> {
+        Some(self.quantum)
+    }
+
+    /// the last virtual runtime picked, then every process' virtual runtime.
+    fn state(&self) -> Vec<u64> {
+        let mut state = vec![self.min_vruntime];
+        state.extend(&self.vruntime);
+        state
+    }
+
+    fn restore_state(&mut self, state: &[u64], processes: &[SimProcess]) {
+        // state saved by another policy, or none at all, leaves every process starting afresh.
+        if state.len() != processes.len() + 1 {
+            return;
+        }
+        self.min_vruntime = state[0];
+        self.vruntime = Vec::from(&state[1..]);
+        // processes are charged whenever they stop running, so the cpu time they were last
+        // charged for is all they have been given.
+        self.served = processes
+            .iter()
+            .map(|process| process.history().iter().map(|burst| burst.length).sum())
+            .collect();
+    }
+}
+
+pub fn fcfs(incoming: Vec<SimProcess>, events: &mut dyn EventSink) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(Fcfs::default()), events)
+}
+
+pub fn sjf(incoming: Vec<SimProcess>, events: &mut dyn EventSink) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(ShortestJobFirst::default()), events)
+}
+
+pub fn priority(incoming: Vec<SimProcess>, events: &mut dyn EventSink) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(PriorityFirst::default()), events)
+}
+
+pub fn round_robin(
+    incoming: Vec<SimProcess>,
+    quantum: u32,
+    events: &mut dyn EventSink,
+) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(RoundRobin::new(quantum)), events)
+}
+
+pub fn priority_rr(
+    incoming: Vec<SimProcess>,
+    quantum: u32,
+    events: &mut dyn EventSink,
+) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(PriorityRoundRobin::new(quantum)), events)
+}
+
+pub fn weighted_fair(
+    incoming: Vec<SimProcess>,
+    quantum: u32,
+    events: &mut dyn EventSink,
+) -> Vec<SimProcess> {
+    sim::simulate(incoming, Box::new(WeightedFair::new(quantum)), events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NullSink;
+    use crate::fixtures;
+
+    fn starts(processes: &[SimProcess], name: &str) -> Vec<u32> {
+        let process = processes.iter().find(|p| p.name == name).unwrap();
+        process.history().iter().map(|burst| burst.start).collect()
+    }
+
+    #[test]
+    fn weighted_fair_shares_by_weight() {
+        // equal weights take turns, one quantum at a time.
+        let finished = weighted_fair(fixtures::workload(&["A,5,4", "B,5,4"]), 2, &mut NullSink);
+        assert_eq!(starts(&finished, "A"), vec![0, 4]);
+        assert_eq!(starts(&finished, "B"), vec![2, 6]);
+
+        // priority 1 weighs 35 times as much as priority 5, so once A has had a quantum, B's
+        // virtual runtime stays below A's until B is done.
+        let finished = weighted_fair(fixtures::workload(&["A,5,20", "B,1,20"]), 2, &mut NullSink);
+        assert_eq!(starts(&finished, "A")[..2], [0, 22]);
+        assert_eq!(starts(&finished, "B")[0], 2);
+    }
 }
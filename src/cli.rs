@@ -0,0 +1,1134 @@
+use crate::document::InputFormat;
+use crate::events::Level;
+use crate::generate::{Distribution, Generator};
+use crate::import::{Import, TraceFormat};
+use crate::metrics::Metric;
+use crate::montecarlo::MonteCarlo;
+use crate::preset;
+use crate::realrun::RealRun;
+use crate::record::{Record, Target};
+use crate::report::Format;
+use crate::sweep::{self, Parameter, Sweep};
+use crate::{Parameters, ProgramError, Result, ScheduleKind};
+
+/// the name the program is invoked as in the help text.
+const PROGRAM: &str = "scheduler";
+
+/// what the program has been asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    /// run the workload once under a single scheduler.
+    Run,
+    /// run the workload under every listed scheduler and compare the results.
+    Compare(Vec<ScheduleKind>),
+    /// run the workload under a single scheduler once for every value of a parameter.
+    Sweep(Sweep),
+    /// write a randomly generated workload.
+    Generate(Generator),
+    /// run schedulers over many generated workloads and estimate every metric.
+    MonteCarlo(MonteCarlo),
+    /// check a workload file without running it.
+    Validate,
+    /// turn a trace of a real system into a workload.
+    Import(Import),
+    /// sample processes running on this machine and write what they did as a workload.
+    Record(Record),
+    /// run the workload as real processes and compare what they measure with the simulation.
+    RealRun(RealRun),
+    /// animate the workload in the terminal, starting with a single scheduler.
+    Tui,
+    /// edit the workload and run schedulers from an interactive prompt.
+    Repl,
+    /// print the help text for one command, or for the program as a whole.
+    Help(Option<&'static str>),
+}
+
+/// a subcommand, the positional arguments it takes and what it does.
+struct Command {
+    name: &'static str,
+    arguments: &'static str,
+    about: &'static str,
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "run",
+        arguments: "<workload>",
+        about: "run one scheduler over a workload, or a scenario file, which takes no options",
+    },
+    Command {
+        name: "compare",
+        arguments: "<workload>",
+        about: "run several schedulers over the same workload and compare them",
+    },
+    Command {
+        name: "sweep",
+        arguments: "<workload>",
+        about: "run one scheduler once for every value of a parameter",
+    },
+    Command {
+        name: "generate",
+        arguments: "",
+        about: "write a random workload",
+    },
+    Command {
+        name: "montecarlo",
+        arguments: "",
+        about: "run schedulers over many random workloads and compare them statistically",
+    },
+    Command {
+        name: "validate",
+        arguments: "<workload>",
+        about: "check a workload file for mistakes",
+    },
+    Command {
+        name: "import",
+        arguments: "<trace>",
+        about: "turn a trace of a real system into a workload",
+    },
+    Command {
+        name: "record",
+        arguments: "[-- <command>...]",
+        about: "record a workload from running processes, or from a command it starts",
+    },
+    Command {
+        name: "realrun",
+        arguments: "<workload>",
+        about: "run a workload as real processes on linux and compare them with the simulation",
+    },
+    Command {
+        name: "tui",
+        arguments: "<workload>",
+        about: "animate a schedule in the terminal",
+    },
+    Command {
+        name: "repl",
+        arguments: "[workload]",
+        about: "build workloads and run schedulers interactively",
+    },
+];
+
+/// a command line option and the commands that accept it.
+struct Opt {
+    long: &'static str,
+    short: Option<char>,
+    /// the placeholder for the option's value, or `None` for a flag.
+    value: Option<&'static str>,
+    about: &'static str,
+    commands: &'static [&'static str],
+}
+
+const SIMULATING: &[&str] = &[
+    "run",
+    "compare",
+    "sweep",
+    "montecarlo",
+    "realrun",
+    "tui",
+    "repl",
+];
+const REPORTING: &[&str] = &["run", "compare", "sweep", "montecarlo"];
+/// the commands that build workloads with the generator.
+const GENERATING: &[&str] = &["generate", "montecarlo"];
+
+const OPTIONS: &[Opt] = &[
+    Opt {
+        long: "workload",
+        short: Some('w'),
+        value: Some("source"),
+        about: "workload file, - for standard input or preset:<name>, instead of the <workload> argument",
+        commands: &["run", "compare", "sweep", "validate", "realrun", "tui", "repl"],
+    },
+    Opt {
+        long: "input-format",
+        short: None,
+        value: Some("format"),
+        about: "workload format: csv, json or toml (default from the file extension, else csv)",
+        commands: &["run", "compare", "sweep", "validate", "realrun", "tui", "repl"],
+    },
+    Opt {
+        long: "algo",
+        short: Some('a'),
+        value: Some("name"),
+        about: "scheduler: fcfs, sjf, priority, rr, priority-rr or fair. compare, montecarlo and realrun take a comma separated list",
+        commands: &["run", "compare", "sweep", "montecarlo", "realrun", "tui"],
+    },
+    Opt {
+        long: "quantum",
+        short: Some('q'),
+        value: Some("ticks"),
+        about: "time quantum of the round-robin schedulers (default 10)",
+        commands: SIMULATING,
+    },
+    Opt {
+        long: "cpus",
+        short: Some('c'),
+        value: Some("count"),
+        about: "number of cpus sharing the ready queue (default 1)",
+        commands: SIMULATING,
+    },
+    Opt {
+        long: "overhead",
+        short: None,
+        value: Some("ticks"),
+        about: "time a cpu spends switching to a different process (default 0)",
+        commands: SIMULATING,
+    },
+    Opt {
+        long: "format",
+        short: Some('f'),
+        value: Some("format"),
+        about: "result format: text, csv or json (default text)",
+        commands: REPORTING,
+    },
+    Opt {
+        long: "output",
+        short: Some('o'),
+        value: Some("file"),
+        about: "write results to a file instead of standard output",
+        commands: &[
+            "run",
+            "compare",
+            "sweep",
+            "generate",
+            "montecarlo",
+            "import",
+            "record",
+            "realrun",
+        ],
+    },
+    Opt {
+        long: "seed",
+        short: Some('s'),
+        value: Some("number"),
+        about: "seed for the random number generator",
+        commands: GENERATING,
+    },
+    Opt {
+        long: "count",
+        short: Some('n'),
+        value: Some("number"),
+        about: "number of processes to generate (default 10)",
+        commands: GENERATING,
+    },
+    Opt {
+        long: "burst",
+        short: None,
+        value: Some("distribution"),
+        about: "distribution of cpu bursts (default uniform:5:100)",
+        commands: GENERATING,
+    },
+    Opt {
+        long: "priority",
+        short: None,
+        value: Some("distribution"),
+        about: "distribution of priorities, kept within 1 to 10 (default uniform:1:10)",
+        commands: GENERATING,
+    },
+    Opt {
+        long: "interarrival",
+        short: None,
+        value: Some("distribution"),
+        about: "distribution of the time between arrivals (default uniform:0:10)",
+        commands: GENERATING,
+    },
+    Opt {
+        long: "parameter",
+        short: None,
+        value: Some("name"),
+        about: "parameter to sweep: quantum, cpus or overhead (default quantum)",
+        commands: &["sweep"],
+    },
+    Opt {
+        long: "range",
+        short: None,
+        value: Some("start:end[:step]"),
+        about: "values to sweep over (default 1:50)",
+        commands: &["sweep"],
+    },
+    Opt {
+        long: "objective",
+        short: None,
+        value: Some("metric"),
+        about: "metric the best value is chosen, or schedulers are compared, by: wait, turnaround, response, switches, makespan or fairness",
+        commands: &["sweep", "montecarlo"],
+    },
+    Opt {
+        long: "runs",
+        short: Some('k'),
+        value: Some("number"),
+        about: "number of workloads to generate, one per seed (default 30)",
+        commands: &["montecarlo"],
+    },
+    Opt {
+        long: "from",
+        short: None,
+        value: Some("format"),
+        about: "trace format: swf, or sched for ftrace and perf script dumps (default swf for .swf files)",
+        commands: &["import"],
+    },
+    Opt {
+        long: "scale",
+        short: None,
+        value: Some("ticks"),
+        about: "ticks per second of trace time (default 1 for swf, 1000 for sched and record)",
+        commands: &["import", "record"],
+    },
+    Opt {
+        long: "window",
+        short: None,
+        value: Some("start:end"),
+        about: "import only this part of the trace, in seconds",
+        commands: &["import"],
+    },
+    Opt {
+        long: "pid",
+        short: Some('p'),
+        value: Some("pid,pid,..."),
+        about: "processes to record, with their children, instead of starting a command",
+        commands: &["record"],
+    },
+    Opt {
+        long: "duration",
+        short: Some('d'),
+        value: Some("seconds"),
+        about: "how long to record for (default 10 for --pid, else until the command exits, which is stopped at the end)",
+        commands: &["record"],
+    },
+    Opt {
+        long: "interval",
+        short: None,
+        value: Some("ms"),
+        about: "time between samples (default 10)",
+        commands: &["record"],
+    },
+    Opt {
+        long: "tick",
+        short: None,
+        value: Some("ms"),
+        about: "how long a tick lasts when run for real (default 1)",
+        commands: &["realrun"],
+    },
+    Opt {
+        long: "trace",
+        short: None,
+        value: Some("file"),
+        about: "write the schedule in the chrome trace event format",
+        commands: &["run", "compare"],
+    },
+    Opt {
+        long: "html",
+        short: None,
+        value: Some("file"),
+        about: "write a self-contained html report",
+        commands: &["run", "compare"],
+    },
+    Opt {
+        long: "verbose",
+        short: Some('v'),
+        value: None,
+        about: "print arrivals and completions; twice (-vv) prints every event",
+        commands: &["run", "compare"],
+    },
+    Opt {
+        long: "log",
+        short: None,
+        value: Some("file"),
+        about: "write the event log to a file",
+        commands: &["run", "compare"],
+    },
+    Opt {
+        long: "snapshot",
+        short: None,
+        value: Some("time:file"),
+        about: "save the simulation state as of a point in time",
+        commands: &["run"],
+    },
+    Opt {
+        long: "restore",
+        short: None,
+        value: Some("file"),
+        about: "resume from a snapshot instead of reading a workload",
+        commands: &["run", "compare"],
+    },
+    Opt {
+        long: "help",
+        short: Some('h'),
+        value: None,
+        about: "show this help",
+        commands: &[
+            "run",
+            "compare",
+            "sweep",
+            "generate",
+            "montecarlo",
+            "validate",
+            "import",
+            "record",
+            "realrun",
+            "tui",
+            "repl",
+        ],
+    },
+];
+
+fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+fn option_label(option: &Opt) -> String {
+    let short = match option.short {
+        Some(short) => format!("-{}, ", short),
+        None => String::from("    "),
+    };
+    match option.value {
+        Some(value) => format!("{}--{} <{}>", short, option.long, value),
+        None => format!("{}--{}", short, option.long),
+    }
+}
+
+/// the help text for one command, or for the whole program, built from the command and option
+/// tables.
+pub fn help_text(command: Option<&str>) -> String {
+    let mut text = String::new();
+    let command = command.and_then(find_command);
+    match command {
+        Some(command) => {
+            // options cannot follow a `--` that ends them.
+            let parts = match command.arguments.starts_with("[--") {
+                true => [PROGRAM, command.name, "[options]", command.arguments],
+                false => [PROGRAM, command.name, command.arguments, "[options]"],
+            };
+            let usage: Vec<&str> = parts.into_iter().filter(|part| !part.is_empty()).collect();
+            text.push_str(&format!(
+                "usage: {}\n\n{}\n",
+                usage.join(" "),
+                command.about
+            ));
+        }
+        None => {
+            text.push_str(&format!(
+                "usage: {} <command> [options]\n\ncommands:\n",
+                PROGRAM
+            ));
+            let usages: Vec<String> = COMMANDS
+                .iter()
+                .map(|command| format!("{} {}", command.name, command.arguments))
+                .collect();
+            // the longest usage decides the column the descriptions start in.
+            let width = usages.iter().map(String::len).max().unwrap_or(0) + 2;
+            for (usage, command) in usages.iter().zip(COMMANDS) {
+                text.push_str(&format!("  {:<width$}{}\n", usage, command.about));
+            }
+        }
+    }
+
+    text.push_str("\noptions:\n");
+    let options = OPTIONS
+        .iter()
+        .filter(|option| command.is_none_or(|command| option.commands.contains(&command.name)));
+    for option in options {
+        text.push_str(&format!("  {:<34}{}\n", option_label(option), option.about));
+    }
+    if command.is_none_or(|command| command.arguments.contains("workload")) {
+        text.push_str("\npresets, used as preset:<name> in place of a workload file:\n");
+        for preset in &preset::PRESETS {
+            text.push_str(&format!("  {:<14}{}\n", preset.name, preset.about));
+        }
+    }
+    if command.is_none() {
+        text.push_str(&format!(
+            "\nrun '{} help <command>' for the options of a single command.\n",
+            PROGRAM
+        ));
+    }
+    text
+}
+
+fn invalid<T>(message: String) -> Result<T> {
+    eprintln!("error: {}", message);
+    Err(ProgramError::InvalidCommandInput)
+}
+
+fn parse_number<T>(value: &str, option: &str, minimum: T) -> Result<T>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    match value.parse::<T>() {
+        Ok(number) if number >= minimum => Ok(number),
+        _ => invalid(format!(
+            "--{} expects a number of at least {}, received '{}'",
+            option, minimum, value
+        )),
+    }
+}
+
+/// parse a snapshot request given as `time:filename`.
+fn parse_snapshot(value: &str) -> Result<(u32, String)> {
+    match value.split_once(':') {
+        Some((time, filename)) if !filename.is_empty() => match time.trim().parse::<u32>() {
+            Ok(time) => Ok((time, String::from(filename))),
+            Err(_) => invalid(format!(
+                "--snapshot expects <time>:<file> with a whole number of ticks, received '{}'",
+                value
+            )),
+        },
+        _ => invalid(format!(
+            "--snapshot expects <time>:<file>, received '{}'",
+            value
+        )),
+    }
+}
+
+/// take the workload from the positional argument or --workload, whichever comes first.
+fn set_workload(config: &mut Configuration, source: &str) -> Result<()> {
+    match config.filename {
+        Some(_) => invalid(format!("unexpected argument '{}'", source)),
+        None => {
+            config.filename = Some(String::from(source));
+            Ok(())
+        }
+    }
+}
+
+/// parse a positive number of seconds or ticks.
+fn parse_positive(value: &str, option: &str) -> Result<f64> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => Ok(number),
+        _ => invalid(format!(
+            "--{} expects a positive number, received '{}'",
+            option, value
+        )),
+    }
+}
+
+/// parse a window of trace time given as `start:end` in seconds.
+fn parse_window(value: &str) -> Result<(f64, f64)> {
+    let parse = |text: &str| text.trim().parse::<f64>().ok().filter(|x| x.is_finite());
+    match value.split_once(':') {
+        Some((start, end)) => match (parse(start), parse(end)) {
+            (Some(start), Some(end)) if start < end => Ok((start, end)),
+            _ => invalid(format!(
+                "invalid window '{}', expected <start:end> with start before end",
+                value
+            )),
+        },
+        None => invalid(format!("invalid window '{}', expected <start:end>", value)),
+    }
+}
+
+fn parse_distribution(value: &str) -> Result<Distribution> {
+    Distribution::try_from(value).or_else(|_| {
+        invalid(format!(
+            "invalid distribution '{}', expected one of uniform:<low>:<high>, exponential:<mean>, \
+             normal:<mean>:<deviation>, bimodal:<low>:<high>:<share> or pareto:<scale>:<shape>",
+            value
+        ))
+    })
+}
+
+fn parse_algorithm(name: &str) -> Result<ScheduleKind> {
+    ScheduleKind::try_from(name).or_else(|_| {
+        let keys: Vec<&str> = ScheduleKind::ALL.iter().map(|kind| kind.key()).collect();
+        invalid(format!(
+            "unknown scheduler '{}', expected one of {}",
+            name,
+            keys.join(", ")
+        ))
+    })
+}
+
+pub struct Configuration {
+    pub mode: Mode,
+    pub scheduler: ScheduleKind,
+    /// the workload to read. absent when restoring a snapshot, generating or starting an empty
+    /// repl.
+    pub filename: Option<String>,
+    /// the format the workload is written in, when not told by its extension.
+    pub input_format: Option<InputFormat>,
+    pub parameters: Parameters,
+    /// the seed for randomness, if one was given.
+    pub seed: Option<u64>,
+    pub format: Format,
+    /// where to write results instead of standard output.
+    pub output: Option<String>,
+    pub trace: Option<String>,
+    pub html: Option<String>,
+    /// how much of the event log to print: nothing, lifecycle events or every event.
+    pub verbosity: u8,
+    /// where to write the event log, if anywhere.
+    pub log: Option<String>,
+    /// when to capture a snapshot of the simulation, and where to save it.
+    pub snapshot: Option<(u32, String)>,
+    /// a snapshot to resume from instead of starting the workload from scratch.
+    pub restore: Option<String>,
+    /// the long names of the options given, in the order they came.
+    pub options: Vec<&'static str>,
+}
+
+impl Configuration {
+    fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            scheduler: ScheduleKind::FCFS,
+            filename: None,
+            input_format: None,
+            parameters: Parameters::default(),
+            seed: None,
+            format: Format::Text,
+            output: None,
+            trace: None,
+            html: None,
+            verbosity: 0,
+            log: None,
+            snapshot: None,
+            restore: None,
+            options: vec![],
+        }
+    }
+
+    pub fn build(args: &[String]) -> Result<Self> {
+        let mut iter = args.iter().skip(1);
+        let command = match iter.next().map(String::as_str) {
+            None | Some("help" | "--help" | "-h") => {
+                let topic = iter.next().and_then(|name| find_command(name));
+                return Ok(Self::new(Mode::Help(topic.map(|command| command.name))));
+            }
+            Some(name) => match find_command(name) {
+                Some(command) => command,
+                None => return invalid(format!("unknown command '{}'", name)),
+            },
+        };
+
+        let mut config = Self::new(Mode::Run);
+        let mut algorithms = vec![];
+        let mut sweep = Sweep::default();
+        let mut generator = Generator::default();
+        let mut experiment = MonteCarlo::default();
+        let mut import = Import::new(TraceFormat::Swf);
+        let mut trace_format = None;
+        let mut record = Record::new(Target::Pids(vec![]));
+        let mut pids = vec![];
+        let mut program = vec![];
+        let mut real = RealRun::default();
+        while let Some(arg) = iter.next() {
+            if arg == "-vv" {
+                config.verbosity += 2;
+                config.options.push("verbose");
+                continue;
+            }
+            if arg == "--" && command.name == "record" {
+                program = iter.by_ref().cloned().collect();
+                break;
+            }
+            let (option, inline) = if let Some(long) = arg.strip_prefix("--") {
+                let (name, inline) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (long, None),
+                };
+                match OPTIONS.iter().find(|option| option.long == name) {
+                    Some(option) => (option, inline),
+                    None => return invalid(format!("unknown option '{}'", arg)),
+                }
+            } else if arg.len() == 2 && arg.starts_with('-') && arg != "--" {
+                let short = arg.chars().nth(1);
+                match OPTIONS.iter().find(|option| option.short == short) {
+                    Some(option) => (option, None),
+                    None => return invalid(format!("unknown option '{}'", arg)),
+                }
+            } else {
+                set_workload(&mut config, arg)?;
+                continue;
+            };
+
+            if !option.commands.contains(&command.name) {
+                return invalid(format!(
+                    "--{} is not an option of {}",
+                    option.long, command.name
+                ));
+            }
+            let value = match (option.value, inline) {
+                (None, None) => String::new(),
+                (None, Some(_)) => return invalid(format!("--{} takes no value", option.long)),
+                (Some(_), Some(value)) => String::from(value),
+                (Some(_), None) => match iter.next() {
+                    Some(value) => String::from(value),
+                    None => return invalid(format!("--{} requires a value", option.long)),
+                },
+            };
+            config.options.push(option.long);
+
+            match option.long {
+                "algo" => {
+                    for name in value.split(',') {
+                        algorithms.push(parse_algorithm(name.trim())?);
+                    }
+                }
+                "quantum" => config.parameters.quantum = parse_number(&value, option.long, 1)?,
+                "cpus" => config.parameters.cpus = parse_number(&value, option.long, 1)?,
+                "overhead" => config.parameters.overhead = parse_number(&value, option.long, 0)?,
+                "format" => {
+                    config.format = Format::try_from(value.as_str()).or_else(|_| {
+                        invalid(format!(
+                            "unknown format '{}', expected text, csv or json",
+                            value
+                        ))
+                    })?
+                }
+                "workload" => set_workload(&mut config, &value)?,
+                "input-format" => {
+                    config.input_format =
+                        Some(InputFormat::try_from(value.as_str()).or_else(|_| {
+                            invalid(format!(
+                                "unknown input format '{}', expected csv, json or toml",
+                                value
+                            ))
+                        })?)
+                }
+                "output" => config.output = Some(value),
+                "seed" => config.seed = Some(parse_number(&value, option.long, 0)?),
+                "count" => generator.count = parse_number(&value, option.long, 1)?,
+                "burst" => generator.burst = parse_distribution(&value)?,
+                "priority" => generator.priority = parse_distribution(&value)?,
+                "interarrival" => generator.interarrival = parse_distribution(&value)?,
+                "parameter" => {
+                    sweep.parameter = Parameter::try_from(value.as_str()).or_else(|_| {
+                        let keys: Vec<&str> = Parameter::ALL.iter().map(|p| p.key()).collect();
+                        invalid(format!(
+                            "unknown parameter '{}', expected one of {}",
+                            value,
+                            keys.join(", ")
+                        ))
+                    })?
+                }
+                "range" => {
+                    (sweep.start, sweep.end, sweep.step) =
+                        sweep::parse_range(&value).or_else(|_| {
+                            invalid(format!(
+                                "invalid range '{}', expected <start:end[:step]>",
+                                value
+                            ))
+                        })?
+                }
+                "objective" => {
+                    sweep.objective = Metric::try_from(value.as_str()).or_else(|_| {
+                        let keys: Vec<&str> = Metric::ALL.iter().map(|m| m.key()).collect();
+                        invalid(format!(
+                            "unknown metric '{}', expected one of {}",
+                            value,
+                            keys.join(", ")
+                        ))
+                    })?
+                }
+                "from" => {
+                    trace_format = Some(TraceFormat::try_from(value.as_str()).or_else(|_| {
+                        let keys: Vec<&str> = TraceFormat::ALL.iter().map(|f| f.key()).collect();
+                        invalid(format!(
+                            "unknown trace format '{}', expected one of {}",
+                            value,
+                            keys.join(", ")
+                        ))
+                    })?)
+                }
+                "scale" => import.scale = Some(parse_positive(&value, option.long)?),
+                "pid" => {
+                    for pid in value.split(',') {
+                        pids.push(parse_number(pid.trim(), option.long, 1)?);
+                    }
+                }
+                "tick" => real.tick = parse_positive(&value, option.long)? / 1000.0,
+                "duration" => record.duration = Some(parse_positive(&value, option.long)?),
+                "interval" => {
+                    record.interval = parse_number::<u64>(&value, option.long, 1)? as f64 / 1000.0
+                }
+                "window" => import.window = Some(parse_window(&value)?),
+                "runs" => experiment.runs = parse_number(&value, option.long, 2)?,
+                "trace" => config.trace = Some(value),
+                "html" => config.html = Some(value),
+                "verbose" => config.verbosity += 1,
+                "log" => config.log = Some(value),
+                "snapshot" => config.snapshot = Some(parse_snapshot(&value)?),
+                "restore" => config.restore = Some(value),
+                "help" => {
+                    config.mode = Mode::Help(Some(command.name));
+                    return Ok(config);
+                }
+                other => unreachable!("option --{} has no handler", other),
+            }
+        }
+
+        let workload_optional = matches!(
+            (command.name, &config.restore),
+            ("generate" | "montecarlo" | "record" | "repl", _) | ("run" | "compare", Some(_))
+        );
+        match (command.name, &config.filename) {
+            ("generate" | "montecarlo" | "record", Some(filename)) => {
+                return invalid(format!("unexpected argument '{}'", filename))
+            }
+            (_, None) if !workload_optional => {
+                return invalid(format!("{} is a required argument", command.arguments))
+            }
+            _ => (),
+        }
+        if ["tui", "repl"].contains(&command.name)
+            && config.filename.as_deref() == Some(crate::STDIN)
+        {
+            return invalid(format!(
+                "{} reads keys from standard input, so the workload cannot come from it",
+                command.name
+            ));
+        }
+        if !["compare", "montecarlo", "realrun"].contains(&command.name) && algorithms.len() > 1 {
+            return invalid(format!("{} runs a single scheduler", command.name));
+        }
+
+        let default_scheduler = match command.name {
+            "sweep" => ScheduleKind::RR,
+            _ => ScheduleKind::FCFS,
+        };
+        config.scheduler = algorithms.last().copied().unwrap_or(default_scheduler);
+        config.mode = match command.name {
+            "compare" if algorithms.is_empty() => Mode::Compare(Vec::from(ScheduleKind::ALL)),
+            "compare" => Mode::Compare(algorithms),
+            "sweep" => {
+                if sweep.start < sweep.parameter.minimum() {
+                    return invalid(format!(
+                        "{} must be at least {}",
+                        sweep.parameter.key(),
+                        sweep.parameter.minimum()
+                    ));
+                }
+                Mode::Sweep(sweep)
+            }
+            "generate" => Mode::Generate(generator),
+            "montecarlo" => {
+                if !algorithms.is_empty() {
+                    experiment.kinds = algorithms;
+                }
+                experiment.generator = generator;
+                experiment.objective = sweep.objective;
+                Mode::MonteCarlo(experiment)
+            }
+            "validate" => Mode::Validate,
+            "import" => {
+                let filename = config.filename.as_deref().unwrap_or_default();
+                import.format = match trace_format.or_else(|| TraceFormat::detect(filename)) {
+                    Some(format) => format,
+                    None => {
+                        return invalid(format!(
+                            "cannot tell the trace format of '{}', name it with --from",
+                            filename
+                        ))
+                    }
+                };
+                Mode::Import(import)
+            }
+            "realrun" => {
+                if !algorithms.is_empty() {
+                    real.kinds = algorithms;
+                }
+                Mode::RealRun(real)
+            }
+            "record" => {
+                record.target = match (pids.is_empty(), program.is_empty()) {
+                    (false, true) => Target::Pids(pids),
+                    (true, false) => Target::Command(program),
+                    (false, false) => {
+                        return invalid(String::from(
+                            "record takes either --pid or a command, not both",
+                        ))
+                    }
+                    (true, true) => {
+                        return invalid(String::from("record needs --pid or a command after --"))
+                    }
+                };
+                record.scale = import.scale.unwrap_or(record.scale);
+                Mode::Record(record)
+            }
+            "tui" => Mode::Tui,
+            "repl" => Mode::Repl,
+            _ => Mode::Run,
+        };
+        Ok(config)
+    }
+
+    /// the most detailed level of event the configured verbosity asks for.
+    pub fn level(&self) -> Level {
+        match self.verbosity {
+            0 | 1 => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    fn build(args: &[&str]) -> Result<Configuration> {
+        let mut full = vec!["scheduler"];
+        full.extend_from_slice(args);
+        Configuration::build(&build_args(&full))
+    }
+
+    #[test]
+    fn build_run_with_named_algorithm() {
+        let config = build(&[
+            "run",
+            "list.txt",
+            "--algo",
+            "rr",
+            "--quantum=4",
+            "-c",
+            "2",
+            "--overhead",
+            "1",
+        ])
+        .unwrap();
+        assert_eq!(config.mode, Mode::Run);
+        assert_eq!(config.scheduler, ScheduleKind::RR);
+        assert_eq!(config.filename.as_deref(), Some("list.txt"));
+        assert_eq!(config.parameters.quantum, 4);
+        assert_eq!(config.parameters.cpus, 2);
+        assert_eq!(config.parameters.overhead, 1);
+        assert_eq!(config.options, ["algo", "quantum", "cpus", "overhead"]);
+    }
+
+    #[test]
+    fn build_rejects_bad_input() {
+        assert!(build(&["run", "list.txt", "--algo", "3"]).is_err());
+        assert!(build(&["run", "list.txt", "--algo", "fcfs,rr"]).is_err());
+        assert!(build(&["run", "list.txt", "--quantum", "0"]).is_err());
+        assert!(build(&["run", "list.txt", "--seed", "4"]).is_err());
+        assert!(build(&["run", "list.txt", "other.txt"]).is_err());
+        assert!(build(&["run", "list.txt", "--trace"]).is_err());
+        assert!(build(&["run", "list.txt", "--verbose=2"]).is_err());
+        assert!(build(&["run"]).is_err());
+        assert!(build(&["launch", "list.txt"]).is_err());
+    }
+
+    #[test]
+    fn build_with_report_options() {
+        let config = build(&[
+            "run", "list.txt", "--trace", "t.json", "--html", "r.html", "-f", "csv", "-o",
+            "out.csv",
+        ])
+        .unwrap();
+        assert_eq!(config.trace.as_deref(), Some("t.json"));
+        assert_eq!(config.html.as_deref(), Some("r.html"));
+        assert_eq!(config.format, Format::Csv);
+        assert_eq!(config.output.as_deref(), Some("out.csv"));
+        assert_eq!(config.input_format, None);
+
+        let config = build(&["run", "tasks.txt", "--input-format", "json"]).unwrap();
+        assert_eq!(config.input_format, Some(InputFormat::Json));
+        assert!(build(&["run", "tasks.txt", "--input-format", "yaml"]).is_err());
+
+        let config = build(&["run", "-", "--algo", "rr"]).unwrap();
+        assert_eq!(config.filename.as_deref(), Some("-"));
+        assert!(build(&["repl", "-"]).is_err());
+    }
+
+    #[test]
+    fn build_verbosity_levels() {
+        let config = build(&["run", "list.txt"]).unwrap();
+        assert_eq!(config.verbosity, 0);
+        let config = build(&["run", "list.txt", "-v"]).unwrap();
+        assert_eq!(config.level(), Level::Info);
+        let config = build(&["run", "list.txt", "-vv", "--log", "run.log"]).unwrap();
+        assert_eq!(config.level(), Level::Debug);
+        assert_eq!(config.log.as_deref(), Some("run.log"));
+        assert_eq!(config.options, ["verbose", "log"]);
+    }
+
+    #[test]
+    fn build_compare_algorithms() {
+        let config = build(&["compare", "list.txt"]).unwrap();
+        assert_eq!(config.mode, Mode::Compare(Vec::from(ScheduleKind::ALL)));
+        let config = build(&["compare", "list.txt", "--algo", "sjf,rr", "-a", "fcfs"]).unwrap();
+        assert_eq!(
+            config.mode,
+            Mode::Compare(vec![
+                ScheduleKind::SJF,
+                ScheduleKind::RR,
+                ScheduleKind::FCFS
+            ])
+        );
+    }
+
+    #[test]
+    fn build_sweep_options() {
+        let config = build(&[
+            "sweep",
+            "list.txt",
+            "--parameter",
+            "cpus",
+            "--range",
+            "1:8",
+            "--objective",
+            "makespan",
+        ])
+        .unwrap();
+        assert_eq!(config.scheduler, ScheduleKind::RR);
+        let sweep = match config.mode {
+            Mode::Sweep(sweep) => sweep,
+            mode => panic!("received unexpected mode: {:?}", mode),
+        };
+        assert_eq!(sweep.parameter, Parameter::Cpus);
+        assert_eq!((sweep.start, sweep.end, sweep.step), (1, 8, 1));
+        assert_eq!(sweep.objective, Metric::Makespan);
+        assert!(build(&["sweep", "list.txt", "--range", "0:10"]).is_err());
+    }
+
+    #[test]
+    fn build_import_options() {
+        let config = build(&[
+            "import", "jobs.swf", "--scale", "0.5", "--window", "60:3600",
+        ])
+        .unwrap();
+        let import = match config.mode {
+            Mode::Import(import) => import,
+            mode => panic!("received unexpected mode: {:?}", mode),
+        };
+        assert_eq!(import.format, TraceFormat::Swf);
+        assert_eq!(
+            (import.scale, import.window),
+            (Some(0.5), Some((60.0, 3600.0)))
+        );
+        assert!(build(&["import", "jobs.log"]).is_err());
+        assert!(build(&["import", "jobs.log", "--from", "sched"]).is_ok());
+        assert!(build(&["import", "jobs.swf", "--window", "10:5"]).is_err());
+        assert!(build(&["import"]).is_err());
+    }
+
+    #[test]
+    fn build_record_targets() {
+        let config = build(&["record", "-p", "12,40", "-d", "2.5", "--interval", "5"]).unwrap();
+        let record = match config.mode {
+            Mode::Record(record) => record,
+            mode => panic!("received unexpected mode: {:?}", mode),
+        };
+        assert_eq!(record.target, Target::Pids(vec![12, 40]));
+        assert_eq!((record.duration, record.interval), (Some(2.5), 0.005));
+        let config = build(&["record", "-o", "make.csv", "--", "make", "-j", "4"]).unwrap();
+        assert_eq!(
+            config.mode,
+            Mode::Record(Record::new(Target::Command(build_args(&[
+                "make", "-j", "4"
+            ]))))
+        );
+        assert!(build(&["record"]).is_err());
+        assert!(build(&["record", "-p", "12", "--", "make"]).is_err());
+        assert!(build(&["record", "make"]).is_err());
+        assert!(build(&["run", "list.txt", "--", "make"]).is_err());
+    }
+
+    #[test]
+    fn build_realrun_kinds() {
+        let config = build(&["realrun", "list.txt", "--tick", "2"]).unwrap();
+        assert_eq!(
+            config.mode,
+            Mode::RealRun(RealRun {
+                kinds: vec![ScheduleKind::Fair],
+                tick: 0.002
+            })
+        );
+        let config = build(&["realrun", "list.txt", "-a", "rr,priority-rr"]).unwrap();
+        match config.mode {
+            Mode::RealRun(real) => assert_eq!(real.kinds.len(), 2),
+            mode => panic!("received unexpected mode: {:?}", mode),
+        }
+        assert!(build(&["realrun", "list.txt", "--tick", "0"]).is_err());
+    }
+
+    #[test]
+    fn build_snapshot_and_restore() {
+        let config = build(&["run", "list.txt", "--snapshot", "40:at-40.snap"]).unwrap();
+        assert_eq!(config.snapshot, Some((40, String::from("at-40.snap"))));
+        let config = build(&["compare", "--restore", "at-40.snap"]).unwrap();
+        assert_eq!(config.restore.as_deref(), Some("at-40.snap"));
+        assert_eq!(config.filename, None);
+        assert!(build(&["run", "list.txt", "--snapshot", "40:"]).is_err());
+        assert!(build(&["run", "list.txt", "--snapshot", "soon:at.snap"]).is_err());
+        assert!(build(&["compare", "list.txt", "--snapshot", "4:f"]).is_err());
+        assert!(build(&["tui", "--restore", "at-40.snap"]).is_err());
+    }
+
+    #[test]
+    fn build_generate_and_validate() {
+        let config = build(&[
+            "generate",
+            "--seed",
+            "42",
+            "-n",
+            "25",
+            "-o",
+            "w.txt",
+            "--burst",
+            "pareto:5:1.5",
+        ])
+        .unwrap();
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.output.as_deref(), Some("w.txt"));
+        match config.mode {
+            Mode::Generate(generator) => {
+                assert_eq!(generator.count, 25);
+                assert_eq!(generator.burst.to_string(), "pareto:5:1.5");
+            }
+            mode => panic!("received unexpected mode: {:?}", mode),
+        }
+        assert!(build(&["generate", "list.txt"]).is_err());
+        assert!(build(&["generate", "--priority", "normal"]).is_err());
+        assert_eq!(
+            build(&["validate", "list.txt"]).unwrap().mode,
+            Mode::Validate
+        );
+        assert_eq!(build(&["repl"]).unwrap().mode, Mode::Repl);
+        let config = build(&["montecarlo", "-k", "50", "-a", "fcfs,sjf", "-n", "20"]).unwrap();
+        match config.mode {
+            Mode::MonteCarlo(experiment) => {
+                assert_eq!(experiment.runs, 50);
+                assert_eq!(experiment.generator.count, 20);
+                assert_eq!(
+                    experiment.kinds,
+                    vec![ScheduleKind::FCFS, ScheduleKind::SJF]
+                );
+            }
+            mode => panic!("received unexpected mode: {:?}", mode),
+        }
+        assert!(build(&["montecarlo", "list.txt"]).is_err());
+        assert!(build(&["montecarlo", "--runs", "1"]).is_err());
+        let config = build(&["compare", "--workload", "preset:convoy"]).unwrap();
+        assert_eq!(config.filename.as_deref(), Some("preset:convoy"));
+        assert!(build(&["run", "list.txt", "-w", "preset:convoy"]).is_err());
+    }
+
+    #[test]
+    fn build_help() {
+        assert_eq!(build(&[]).unwrap().mode, Mode::Help(None));
+        assert_eq!(
+            build(&["help", "sweep"]).unwrap().mode,
+            Mode::Help(Some("sweep"))
+        );
+        assert_eq!(
+            build(&["run", "--help"]).unwrap().mode,
+            Mode::Help(Some("run"))
+        );
+    }
+
+    #[test]
+    fn help_lists_only_applicable_options() {
+        let text = help_text(Some("generate"));
+        assert!(text.starts_with("usage: scheduler generate [options]"));
+        assert!(text.contains("  -s, --seed <number>"));
+        assert!(!text.contains("--algo"));
+        let text = help_text(None);
+        assert!(COMMANDS.iter().all(|command| text.contains(command.about)));
+        assert!(text.contains("record [-- <command>...]  "));
+        assert!(OPTIONS.iter().all(|option| text.contains(option.about)));
+    }
+}
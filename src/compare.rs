@@ -0,0 +1,115 @@
+use crate::metrics::{self, Metric, Summary};
+use crate::sim::SimProcess;
+
+/// the metrics shown in the comparison table, in column order.
+const COLUMNS: [Metric; 5] = [
+    Metric::Wait,
+    Metric::Turnaround,
+    Metric::Response,
+    Metric::ContextSwitches,
+    Metric::Fairness,
+];
+
+/// marks the cell holding the best value of its column.
+const BEST_MARKER: char = '*';
+
+fn best(values: &[f64], metric: Metric) -> Option<f64> {
+    values
+        .iter()
+        .copied()
+        .reduce(|a, b| match metric.is_better(b, a) {
+            true => b,
+            false => a,
+        })
+}
+
+/// render a table comparing the summary metrics of several finished schedules of the same
+/// workload. the best value in each column is flagged with an asterisk; ties are all flagged.
+pub fn comparison_table(runs: &[(String, Vec<SimProcess>)]) -> String {
+    let summaries: Vec<Summary> = runs
+        .iter()
+        .map(|(_, processes)| metrics::summarize(processes))
+        .collect();
+    let label_width = runs
+        .iter()
+        .map(|(label, _)| label.len())
+        .chain(std::iter::once("Algorithm".len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut table = format!("{:<width$}", "Algorithm", width = label_width);
+    for column in &COLUMNS {
+        table.push_str(&format!(" | {:>16}", column.header()));
+    }
+    table.push('\n');
+    table.push_str(&"-".repeat(label_width + COLUMNS.len() * 19));
+    table.push('\n');
+
+    let bests: Vec<Option<f64>> = COLUMNS
+        .iter()
+        .map(|column| {
+            let values: Vec<f64> = summaries.iter().map(|s| column.value(s)).collect();
+            best(&values, *column)
+        })
+        .collect();
+
+    for ((label, _), summary) in runs.iter().zip(&summaries) {
+        table.push_str(&format!("{:<width$}", label, width = label_width));
+        for (column, best) in COLUMNS.iter().zip(&bests) {
+            let value = column.value(summary);
+            let marker = match Some(value) == *best {
+                true => BEST_MARKER,
+                false => ' ',
+            };
+            let cell = format!(
+                "{:.precision$}{}",
+                value,
+                marker,
+                precision = column.precision()
+            );
+            table.push_str(&format!(" | {:>16}", cell));
+        }
+        table.push('\n');
+    }
+    table.push_str(&format!("{} best in column\n", BEST_MARKER));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn row<'a>(table: &'a str, label: &str) -> &'a str {
+        table.lines().find(|line| line.starts_with(label)).unwrap()
+    }
+
+    #[test]
+    fn best_value_per_column_is_marked() {
+        // a long job ahead of a short one, which round robin lets through early.
+        let lines = ["T1,1,30", "T2,1,5"];
+        let runs = vec![
+            (String::from("FCFS"), fixtures::fcfs(&lines)),
+            (String::from("RR"), fixtures::round_robin(&lines, 10)),
+        ];
+        let table = comparison_table(&runs);
+        // fcfs: waits of 0 and 30, rr: waits of 5 and 10.
+        assert!(row(&table, "RR").contains("7.50*"));
+        assert!(row(&table, "FCFS").contains("15.00 "));
+        // fcfs never preempts, so it switches the least.
+        assert!(row(&table, "FCFS").contains(" 1*"));
+    }
+
+    #[test]
+    fn ties_are_all_marked() {
+        let lines = ["T1,1,6", "T2,1,4"];
+        let runs = vec![
+            (String::from("X"), fixtures::fcfs(&lines)),
+            (String::from("Y"), fixtures::fcfs(&lines)),
+        ];
+        let table = comparison_table(&runs);
+        // waits of 0 and 6 in both.
+        assert!(row(&table, "X").contains("3.00*"));
+        assert!(row(&table, "Y").contains("3.00*"));
+    }
+}
@@ -0,0 +1,117 @@
+//! errors in workload files that point at the offending text.
+//!
+//! a diagnostic is displayed the way the rust compiler reports errors:
+//!
+//! ```text
+//! invalid priority 'x': invalid digit found in string
+//!  --> process-list.txt:3:5
+//!   |
+//! 3 | T3, x, 25
+//!   |     ^ priority
+//! ```
+
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// the file the text came from, empty when it did not come from a file.
+    pub file: String,
+    /// the line number, starting at 1, or 0 when unknown.
+    pub line: usize,
+    /// the character the problem starts at, starting at 0.
+    pub column: usize,
+    /// how many characters to underline.
+    pub length: usize,
+    /// the field at fault, if the problem lies in a single field.
+    pub field: Option<String>,
+    /// the whole line the problem was found in.
+    pub text: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// a problem with the characters `column..column + length` of `text`.
+    pub fn new(text: &str, column: usize, length: usize, message: String) -> Self {
+        Self {
+            file: String::new(),
+            line: 0,
+            column,
+            length: length.max(1),
+            field: None,
+            text: String::from(text),
+            message,
+        }
+    }
+
+    /// a problem with the line as a whole.
+    pub fn line(text: &str, message: String) -> Self {
+        Self::new(text, 0, text.chars().count(), message)
+    }
+
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(String::from(field));
+        self
+    }
+
+    /// record where the text was read from.
+    pub fn at(mut self, file: &str, line: usize) -> Self {
+        self.file = String::from(file);
+        self.line = line;
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        let number = match self.line {
+            0 => String::new(),
+            line => line.to_string(),
+        };
+        let gutter = " ".repeat(number.len());
+        match (self.file.is_empty(), self.line) {
+            (true, 0) => (),
+            (true, line) => writeln!(f, "{}--> line {}:{}", gutter, line, self.column + 1)?,
+            (false, 0) => writeln!(f, "{}--> {}", gutter, self.file)?,
+            (false, line) => writeln!(
+                f,
+                "{}--> {}:{}:{}",
+                gutter,
+                self.file,
+                line,
+                self.column + 1
+            )?,
+        }
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, self.text)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column),
+            "^".repeat(self.length)
+        )?;
+        if let Some(field) = &self.field {
+            write!(f, " {}", field)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_points_at_the_field() {
+        let diagnostic = Diagnostic::new("T3, x, 25", 4, 1, String::from("invalid priority 'x'"))
+            .with_field("priority")
+            .at("process-list.txt", 3);
+        assert_eq!(
+            diagnostic.to_string(),
+            "invalid priority 'x'\n --> process-list.txt:3:5\n  |\n3 | T3, x, 25\n  |     ^ priority"
+        );
+        let diagnostic = Diagnostic::line("T3", String::from("missing fields")).at("list.txt", 12);
+        assert!(diagnostic.to_string().ends_with("\n12 | T3\n   | ^^"));
+    }
+}
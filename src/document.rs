@@ -0,0 +1,297 @@
+//! workloads written as json or toml documents.
+//!
+//! each task is a table of named fields. only `name`, `priority` and `bursts` are required;
+//! `bursts` is the cpu time, or a list alternating cpu and i/o time that starts and ends with cpu
+//! time:
+//!
+//! ```toml
+//! [[task]]
+//! name = "backup"
+//! priority = 8
+//! bursts = [20, 5, 10]   # cpu 20, i/o 5, cpu 10
+//! arrival = 4
+//! deadline = 100
+//! group = "ops"
+//! tickets = 50
+//! affinity = [0, 1]
+//! processors = 4         # cpus a parallel job asks for
+//! ```
+//!
+//! in json the tasks are an array, either the whole document or its `tasks` member:
+//! `{"tasks": [{"name": "backup", "priority": 8, "bursts": [20, 5, 10]}]}`.
+
+use crate::sim::SimProcess;
+use crate::toml::{self, check_keys, integer, number, string, Table, Value};
+use crate::{json, ProgramError, Result};
+use std::io::BufRead;
+
+/// the formats a workload can be read from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    /// one process per line, as described in [`crate::workload`].
+    Csv,
+    Json,
+    Toml,
+}
+
+impl InputFormat {
+    pub const ALL: [InputFormat; 3] = [InputFormat::Csv, InputFormat::Json, InputFormat::Toml];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            InputFormat::Csv => "csv",
+            InputFormat::Json => "json",
+            InputFormat::Toml => "toml",
+        }
+    }
+
+    /// the format a workload source is written in, judging by its extension. anything that is
+    /// not `.json` or `.toml` is taken to be comma-separated.
+    pub fn detect(source: &str) -> Self {
+        let extension = std::path::Path::new(source)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => InputFormat::Json,
+            "toml" => InputFormat::Toml,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+impl TryFrom<&str> for InputFormat {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        InputFormat::ALL
+            .into_iter()
+            .find(|format| format.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
+}
+
+/// the fields a task may have.
+const FIELDS: [&str; 9] = [
+    "name",
+    "priority",
+    "bursts",
+    "arrival",
+    "deadline",
+    "group",
+    "tickets",
+    "affinity",
+    "processors",
+];
+
+fn invalid<T>(message: String) -> Result<T> {
+    Err(ProgramError::InvalidDocument(message))
+}
+
+/// read a list of numbers that are at least `minimum`.
+fn numbers<T>(table: &Table, key: &str, context: &str, minimum: T) -> Result<Option<Vec<T>>>
+where
+    T: TryFrom<i64> + PartialOrd + std::fmt::Display + Copy,
+{
+    let items = match table.get(key) {
+        None => return Ok(None),
+        Some(Value::Array(items)) => items,
+        Some(value) => {
+            return invalid(format!(
+                "'{}' in {} should be an array, not {}",
+                key,
+                context,
+                value.type_name()
+            ))
+        }
+    };
+    items
+        .iter()
+        .map(|item| integer(item, key, context, minimum))
+        .collect::<Result<Vec<T>>>()
+        .map(Some)
+}
+
+fn task(table: &Table, index: usize) -> Result<SimProcess> {
+    let context = format!("task {}", index + 1);
+    check_keys(table, &FIELDS, &context)?;
+    let name = match string(table, "name", &context)? {
+        Some(name) if !name.is_empty() => String::from(name),
+        _ => return invalid(format!("{} needs a name", context)),
+    };
+    let context = format!("task {} ('{}')", index + 1, name);
+    let priority = match number::<u8>(table, "priority", &context, 0)? {
+        Some(priority) => priority,
+        None => return invalid(format!("{} needs a priority", context)),
+    };
+    let bursts = match table.get("bursts") {
+        Some(Value::Array(_)) => numbers(table, "bursts", &context, 0)?.unwrap_or_default(),
+        Some(value) => vec![integer(value, "bursts", &context, 0)?],
+        None => return invalid(format!("{} needs bursts", context)),
+    };
+    if bursts.len() % 2 == 0 {
+        return invalid(format!(
+            "'bursts' in {} should alternate cpu and i/o time, starting and ending with cpu time",
+            context
+        ));
+    }
+
+    let mut process = SimProcess::new(name, priority, bursts[0]);
+    for pair in bursts[1..].chunks(2) {
+        process = process.with_io(pair[0], pair[1]);
+    }
+    if let Some(arrival) = number(table, "arrival", &context, 0)? {
+        process = process.with_arrival(arrival);
+    }
+    process.deadline = number(table, "deadline", &context, 0)?;
+    process.group = string(table, "group", &context)?.map(String::from);
+    process.tickets = number(table, "tickets", &context, 1)?;
+    process.affinity = numbers(table, "affinity", &context, 0)?.unwrap_or_default();
+    process.processors = number(table, "processors", &context, 1)?;
+    Ok(process)
+}
+
+fn tasks(items: &[Value]) -> Result<Vec<SimProcess>> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| match item {
+            Value::Table(table) => task(table, index),
+            other => invalid(format!(
+                "task {} should be a table, not {}",
+                index + 1,
+                other.type_name()
+            )),
+        })
+        .collect()
+}
+
+/// build the workload a json document describes.
+pub fn parse_json(text: &str) -> Result<Vec<SimProcess>> {
+    match json::parse(text)? {
+        Value::Array(items) => tasks(&items),
+        Value::Table(root) => {
+            check_keys(&root, &["tasks"], "the workload")?;
+            match root.get("tasks") {
+                Some(Value::Array(items)) => tasks(items),
+                Some(_) => invalid(String::from("'tasks' should be an array of tasks")),
+                None => invalid(String::from("the workload needs a 'tasks' array")),
+            }
+        }
+        other => invalid(format!(
+            "a workload should be an array of tasks, not {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// build the workload a toml document describes.
+pub fn parse_toml(text: &str) -> Result<Vec<SimProcess>> {
+    let root = toml::parse(text)?;
+    check_keys(&root, &["task"], "the workload")?;
+    let items = toml::tables(&root, "task")?;
+    if items.is_empty() {
+        return invalid(String::from("the workload needs at least one [[task]]"));
+    }
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, table)| task(table, index))
+        .collect()
+}
+
+/// read a workload written in `format` from `reader`, naming `source` in any error.
+pub fn read<R: BufRead>(
+    source: &str,
+    mut reader: R,
+    format: InputFormat,
+) -> Result<Vec<SimProcess>> {
+    let parse = match format {
+        InputFormat::Csv => return crate::read_processes(source, reader),
+        InputFormat::Json => parse_json,
+        InputFormat::Toml => parse_toml,
+    };
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    parse(&text).map_err(|e| match e {
+        ProgramError::InvalidDocument(message) => {
+            ProgramError::InvalidDocument(format!("{}: {}", source, message))
+        }
+        e => e,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_and_toml_describe_the_same_tasks() -> Result<()> {
+        let json = r#"{"tasks": [
+    {"name": "backup", "priority": 8, "bursts": [20, 5, 10], "arrival": 4, "deadline": 100,
+     "group": "ops", "tickets": 50, "affinity": [0, 1]},
+    {"name": "report", "priority": 3, "bursts": 40, "deadline": null}
+]}"#;
+        let toml = "\
+[[task]]
+name = \"backup\"
+priority = 8
+bursts = [20, 5, 10]
+arrival = 4
+deadline = 100
+group = \"ops\"
+tickets = 50
+affinity = [0, 1]
+
+[[task]]
+name = \"report\"
+priority = 3
+bursts = 40
+";
+        let from_json = parse_json(json)?;
+        assert_eq!(from_json, parse_toml(toml)?);
+        let backup = &from_json[0];
+        assert_eq!((backup.total_burst(), backup.total_io()), (30, 5));
+        assert_eq!((backup.arrival, backup.deadline), (4, Some(100)));
+        assert_eq!(backup.group.as_deref(), Some("ops"));
+        assert_eq!(
+            (backup.tickets, backup.affinity.clone()),
+            (Some(50), vec![0, 1])
+        );
+        assert_eq!(from_json[1].deadline, None);
+        assert_eq!(
+            parse_json(r#"[{"name": "a", "priority": 1, "bursts": [5]}]"#)?.len(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reject_malformed_tasks() {
+        let message = |text: &str| match parse_json(text) {
+            Err(ProgramError::InvalidDocument(message)) => message,
+            other => panic!("received unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            message(r#"[{"name": "a", "priority": 1}]"#),
+            "task 1 ('a') needs bursts"
+        );
+        assert_eq!(
+            message(r#"[{"name": "a", "priority": 300, "bursts": 1}]"#),
+            "'priority' in task 1 ('a') is too large: 300"
+        );
+        assert!(
+            message(r#"[{"name": "a", "priority": 1, "bursts": [1, 2]}]"#)
+                .starts_with("'bursts' in task 1 ('a') should alternate")
+        );
+        assert!(
+            message(r#"[{"name": "a", "priority": 1, "bursts": 1, "nice": 2}]"#)
+                .starts_with("unknown key 'nice' in task 1")
+        );
+        assert_eq!(
+            message(r#"[{"name": "a", "priority": 1, "bursts": 1, "affinity": [0, -1]}]"#),
+            "'affinity' in task 1 ('a') should be at least 0, not -1"
+        );
+        assert_eq!(InputFormat::detect("runs/jobs.JSON"), InputFormat::Json);
+        assert_eq!(InputFormat::detect("preset:web"), InputFormat::Csv);
+    }
+}
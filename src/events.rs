@@ -0,0 +1,225 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Stdout, Write};
+
+/// how noteworthy an event is. sinks only record events at or below their configured level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// process lifecycle milestones: arrivals and completions.
+    Info,
+    /// every scheduling decision: dispatches, preemptions, blocking and idle time.
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// a process became known to the scheduler.
+    Arrival,
+    /// a process was given the cpu.
+    Dispatch,
+    /// a process was taken off the cpu with work remaining.
+    Preempt,
+    /// a process left the cpu to wait on something other than the cpu.
+    Block,
+    /// a process finished all of its work.
+    Complete,
+    /// the cpu had nothing to run.
+    Idle,
+}
+
+impl EventKind {
+    pub fn level(&self) -> Level {
+        match self {
+            EventKind::Arrival | EventKind::Complete => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EventKind::Arrival => "arrival",
+            EventKind::Dispatch => "dispatch",
+            EventKind::Preempt => "preempt",
+            EventKind::Block => "block",
+            EventKind::Complete => "complete",
+            EventKind::Idle => "idle",
+        };
+        f.pad(name)
+    }
+}
+
+/// something that happened during a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub time: u32,
+    pub kind: EventKind,
+    /// the process involved, absent for events such as idling that concern no process.
+    pub process: Option<String>,
+}
+
+impl Event {
+    pub fn new(time: u32, kind: EventKind, process: &str) -> Self {
+        Self {
+            time,
+            kind,
+            process: Some(String::from(process)),
+        }
+    }
+
+    pub fn idle(time: u32) -> Self {
+        Self {
+            time,
+            kind: EventKind::Idle,
+            process: None,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Time: {:06} | {:<8} | {}",
+            self.time,
+            self.kind,
+            self.process.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+/// a destination for the events produced by a simulation.
+pub trait EventSink {
+    fn record(&mut self, event: Event);
+}
+
+/// discards every event.
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn record(&mut self, _event: Event) {}
+}
+
+/// keeps every event in memory for later inspection.
+#[derive(Default)]
+pub struct MemorySink {
+    pub events: Vec<Event>,
+}
+
+impl EventSink for MemorySink {
+    fn record(&mut self, event: Event) {
+        self.events.push(event);
+    }
+}
+
+/// writes one line per event, up to a maximum level, to any writer.
+pub struct WriterSink<W: Write> {
+    writer: W,
+    level: Level,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(writer: W, level: Level) -> Self {
+        Self { writer, level }
+    }
+}
+
+impl WriterSink<Stdout> {
+    pub fn stdout(level: Level) -> Self {
+        Self::new(io::stdout(), level)
+    }
+}
+
+impl WriterSink<BufWriter<fs::File>> {
+    pub fn file(path: &str, level: Level) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(fs::File::create(path)?), level))
+    }
+}
+
+impl<W: Write> EventSink for WriterSink<W> {
+    fn record(&mut self, event: Event) {
+        if event.kind.level() <= self.level {
+            // the event log is diagnostic output; failing to write it must not abort a run.
+            let _ = writeln!(self.writer, "{}", event);
+        }
+    }
+}
+
+impl<S: EventSink + ?Sized> EventSink for Box<S> {
+    fn record(&mut self, event: Event) {
+        (**self).record(event);
+    }
+}
+
+/// forwards every event to each of several sinks.
+impl<S: EventSink> EventSink for Vec<S> {
+    fn record(&mut self, event: Event) {
+        for sink in self.iter_mut() {
+            sink.record(event.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo;
+    use crate::fixtures;
+
+    #[test]
+    fn round_robin_events_in_order() {
+        let mut sink = MemorySink::default();
+        algo::round_robin(fixtures::workload(&["A,1,12", "B,1,4"]), 8, &mut sink);
+        let observed: Vec<(u32, EventKind, &str)> = sink
+            .events
+            .iter()
+            .map(|e| (e.time, e.kind, e.process.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            observed,
+            vec![
+                (0, EventKind::Arrival, "A"),
+                (0, EventKind::Arrival, "B"),
+                (0, EventKind::Dispatch, "A"),
+                (8, EventKind::Preempt, "A"),
+                (8, EventKind::Dispatch, "B"),
+                (12, EventKind::Complete, "B"),
+                (12, EventKind::Dispatch, "A"),
+                (16, EventKind::Complete, "A"),
+            ]
+        );
+    }
+
+    #[test]
+    fn writer_sink_filters_by_level() {
+        let mut buffer: Vec<u8> = vec![];
+        {
+            let mut sink = WriterSink::new(&mut buffer, Level::Info);
+            algo::fcfs(fixtures::workload(&["A,1,7", "B,1,2"]), &mut sink);
+        }
+        let log = String::from_utf8(buffer).unwrap();
+        // two arrivals and two completions, without the dispatches.
+        assert_eq!(log.lines().count(), 4);
+        assert!(!log.contains("dispatch"));
+        assert!(log.contains("Time: 000007 | complete | A"));
+    }
+
+    #[test]
+    fn fanout_reaches_every_sink() {
+        let mut sinks = vec![MemorySink::default(), MemorySink::default()];
+        sinks.record(Event::idle(3));
+        for sink in sinks {
+            assert_eq!(sink.events, vec![Event::idle(3)]);
+        }
+    }
+
+    #[test]
+    fn event_display() {
+        assert_eq!(Event::idle(3).to_string(), "Time: 000003 | idle     | -");
+        assert_eq!(
+            Event::new(40, EventKind::Preempt, "T5").to_string(),
+            "Time: 000040 | preempt  | T5"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! workloads shared by the tests of several modules.
+
+use crate::algo;
+use crate::events::NullSink;
+use crate::sim::SimProcess;
+
+/// processes from workload lines such as `"T1,1,15"`.
+pub(crate) fn workload(lines: &[&str]) -> Vec<SimProcess> {
+    lines
+        .iter()
+        .map(|line| SimProcess::try_from(*line).unwrap())
+        .collect()
+}
+
+/// the processes on the lines after round robin has finished them with the quantum.
+pub(crate) fn round_robin(lines: &[&str], quantum: u32) -> Vec<SimProcess> {
+    algo::round_robin(workload(lines), quantum, &mut NullSink)
+}
+
+/// the processes on the lines after first come first served has finished them.
+pub(crate) fn fcfs(lines: &[&str]) -> Vec<SimProcess> {
+    algo::fcfs(workload(lines), &mut NullSink)
+}
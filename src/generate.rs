@@ -0,0 +1,310 @@
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::fmt::Display;
+use std::result;
+
+/// a small, fast pseudo random number generator (splitmix64). the same seed always produces the
+/// same sequence, which keeps generated workloads reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// a uniformly distributed value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// a uniformly distributed integer in `[low, high]`.
+    pub fn range(&mut self, low: u32, high: u32) -> u32 {
+        let span = (high - low) as u64 + 1;
+        low + (self.next_u64() % span) as u32
+    }
+}
+
+/// a probability distribution values of a generated workload are drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// every whole number from `low` to `high` is equally likely.
+    Uniform {
+        low: f64,
+        high: f64,
+    },
+    /// mostly small values with a long tail, like the gaps between independent arrivals.
+    Exponential {
+        mean: f64,
+    },
+    Normal {
+        mean: f64,
+        deviation: f64,
+    },
+    /// values cluster around `low` or, with probability `share`, around `high`; e.g. short
+    /// interactive bursts mixed with long batch ones.
+    Bimodal {
+        low: f64,
+        high: f64,
+        share: f64,
+    },
+    /// a heavy tail: most values are close to `scale` but a few are huge. smaller `shape`s make
+    /// the tail heavier.
+    Pareto {
+        scale: f64,
+        shape: f64,
+    },
+}
+
+impl Distribution {
+    pub const KEYS: [&'static str; 5] = ["uniform", "exponential", "normal", "bimodal", "pareto"];
+
+    /// draw one value.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        match *self {
+            Distribution::Uniform { low, high } => low + rng.next_f64() * (high - low),
+            Distribution::Exponential { mean } => -mean * (1.0 - rng.next_f64()).ln(),
+            Distribution::Normal { mean, deviation } => mean + deviation * standard_normal(rng),
+            Distribution::Bimodal { low, high, share } => {
+                let mode = match rng.next_f64() < share {
+                    true => high,
+                    false => low,
+                };
+                mode + mode / 4.0 * standard_normal(rng)
+            }
+            Distribution::Pareto { scale, shape } => {
+                scale / (1.0 - rng.next_f64()).powf(1.0 / shape)
+            }
+        }
+    }
+
+    /// draw one whole number, clamped to `[minimum, maximum]`.
+    pub fn sample_whole(&self, rng: &mut Rng, minimum: u32, maximum: u32) -> u32 {
+        let value = match *self {
+            Distribution::Uniform { low, high } => {
+                let low = low.round().clamp(minimum as f64, maximum as f64) as u32;
+                let high = high.round().clamp(minimum as f64, maximum as f64) as u32;
+                return rng.range(low, high.max(low));
+            }
+            _ => self.sample(rng).round(),
+        };
+        value.clamp(minimum as f64, maximum as f64) as u32
+    }
+}
+
+/// a normally distributed value with mean 0 and standard deviation 1 (box-muller).
+fn standard_normal(rng: &mut Rng) -> f64 {
+    let radius = (-2.0 * (1.0 - rng.next_f64()).ln()).sqrt();
+    radius * (2.0 * std::f64::consts::PI * rng.next_f64()).cos()
+}
+
+/// parse a distribution written as its name followed by its parameters, separated by colons:
+/// `uniform:5:100`, `exponential:20`, `normal:50:10`, `bimodal:10:200:0.2` or `pareto:5:1.5`.
+impl TryFrom<&str> for Distribution {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        let mut parts = value.split(':');
+        let name = parts.next().unwrap_or_default();
+        let numbers = parts
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<result::Result<Vec<f64>, _>>()
+            .map_err(|_| ProgramError::InvalidCommandInput)?;
+        let distribution = match (name, &numbers[..]) {
+            ("uniform", &[low, high]) if 0.0 <= low && low <= high => {
+                Distribution::Uniform { low, high }
+            }
+            ("exponential", &[mean]) if mean > 0.0 => Distribution::Exponential { mean },
+            ("normal", &[mean, deviation]) if deviation >= 0.0 => {
+                Distribution::Normal { mean, deviation }
+            }
+            ("bimodal", &[low, high, share]) if (0.0..=1.0).contains(&share) => {
+                Distribution::Bimodal { low, high, share }
+            }
+            ("pareto", &[scale, shape]) if scale > 0.0 && shape > 0.0 => {
+                Distribution::Pareto { scale, shape }
+            }
+            _ => return Err(ProgramError::InvalidCommandInput),
+        };
+        Ok(distribution)
+    }
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distribution::Uniform { low, high } => write!(f, "uniform:{}:{}", low, high),
+            Distribution::Exponential { mean } => write!(f, "exponential:{}", mean),
+            Distribution::Normal { mean, deviation } => write!(f, "normal:{}:{}", mean, deviation),
+            Distribution::Bimodal { low, high, share } => {
+                write!(f, "bimodal:{}:{}:{}", low, high, share)
+            }
+            Distribution::Pareto { scale, shape } => write!(f, "pareto:{}:{}", scale, shape),
+        }
+    }
+}
+
+/// the shape of a randomly generated workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generator {
+    /// how many processes to generate.
+    pub count: usize,
+    /// the length of each cpu burst, at least 1.
+    pub burst: Distribution,
+    /// the priority of each process, kept within 1 to 10.
+    pub priority: Distribution,
+    /// the time between one arrival and the next.
+    pub interarrival: Distribution,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            burst: Distribution::Uniform {
+                low: 5.0,
+                high: 100.0,
+            },
+            priority: Distribution::Uniform {
+                low: 1.0,
+                high: 10.0,
+            },
+            interarrival: Distribution::Uniform {
+                low: 0.0,
+                high: 10.0,
+            },
+        }
+    }
+}
+
+/// generate a workload with processes named `T1`, `T2`, ... in arrival order. the first process
+/// arrives at time 0.
+pub fn generate(generator: &Generator, seed: u64) -> Result<Vec<SimProcess>> {
+    let mut rng = Rng::new(seed);
+    let mut arrival: u32 = 0;
+    (1..=generator.count)
+        .map(|index| {
+            let priority = generator.priority.sample_whole(&mut rng, 1, 10);
+            let burst = generator.burst.sample_whole(&mut rng, 1, u32::MAX / 2);
+            let line = format!("T{},{},{},{}", index, priority, burst, arrival);
+            let gap = generator
+                .interarrival
+                .sample_whole(&mut rng, 0, u32::MAX / 2);
+            arrival = arrival.saturating_add(gap);
+            SimProcess::try_from(line.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_workload() {
+        let generator = Generator::default();
+        let first = generate(&generator, 7).unwrap();
+        let second = generate(&generator, 7).unwrap();
+        let other = generate(&generator, 8).unwrap();
+        let bursts = |processes: &[SimProcess]| -> Vec<(u32, u32)> {
+            processes
+                .iter()
+                .map(|process| (process.total_burst(), process.arrival))
+                .collect()
+        };
+        assert_eq!(bursts(&first), bursts(&second));
+        assert_ne!(bursts(&first), bursts(&other));
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let generator = Generator {
+            count: 200,
+            ..Generator::default()
+        };
+        let processes = generate(&generator, 1).unwrap();
+        assert_eq!(processes.len(), 200);
+        assert!(processes
+            .iter()
+            .all(|process| (5..=100).contains(&process.total_burst())
+                && (1..=10).contains(&process.priority)));
+        assert!(processes
+            .windows(2)
+            .all(|pair| pair[0].arrival <= pair[1].arrival));
+    }
+
+    #[test]
+    fn distributions_have_their_mean() {
+        let mean = |distribution: &str| {
+            let distribution = Distribution::try_from(distribution).unwrap();
+            let mut rng = Rng::new(3);
+            (0..20_000)
+                .map(|_| distribution.sample(&mut rng))
+                .sum::<f64>()
+                / 20_000.0
+        };
+        assert!((mean("uniform:0:10") - 5.0).abs() < 0.2);
+        assert!((mean("exponential:20") - 20.0).abs() < 1.0);
+        assert!((mean("normal:50:10") - 50.0).abs() < 0.5);
+        assert!((mean("bimodal:10:110:0.2") - 30.0).abs() < 1.5);
+        // shape 3 keeps the variance finite; the mean is scale * shape / (shape - 1).
+        assert!((mean("pareto:10:3") - 15.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn parse_distributions() {
+        for text in [
+            "uniform:5:100",
+            "exponential:20",
+            "bimodal:10:200:0.2",
+            "pareto:5:1.5",
+        ] {
+            assert_eq!(Distribution::try_from(text).unwrap().to_string(), text);
+        }
+        for text in [
+            "uniform:10:5",
+            "exponential",
+            "normal:5",
+            "pareto:0:1",
+            "zipf:2",
+            "bimodal:1:2:3",
+        ] {
+            assert!(Distribution::try_from(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn generated_workload_reads_back() {
+        let generator = Generator {
+            count: 50,
+            burst: Distribution::try_from("pareto:2:1.2").unwrap(),
+            priority: Distribution::try_from("normal:5:3").unwrap(),
+            interarrival: Distribution::try_from("exponential:4").unwrap(),
+        };
+        let processes = generate(&generator, 11).unwrap();
+        let mut buffer: Vec<u8> = vec![];
+        crate::write_processes(&mut buffer, &processes).unwrap();
+        let read = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(SimProcess::try_from)
+            .collect::<Result<Vec<SimProcess>>>()
+            .unwrap();
+        assert_eq!(read.len(), 50);
+        assert!(read
+            .iter()
+            .zip(&processes)
+            .all(|(read, generated)| read.name == generated.name
+                && read.total_burst() == generated.total_burst()
+                && read.arrival == generated.arrival));
+    }
+}
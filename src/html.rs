@@ -0,0 +1,292 @@
+use crate::metrics::{self, ProcessMetrics};
+use crate::sim::SimProcess;
+use std::io::{self, Write};
+
+const CHART_WIDTH: u32 = 900;
+const CHART_LABEL_WIDTH: u32 = 80;
+const CHART_ROW_HEIGHT: u32 = 22;
+const CHART_AXIS_HEIGHT: u32 = 24;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #bbb; padding: 4px 10px; text-align: right; }
+th { background: #eee; cursor: pointer; user-select: none; }
+td:first-child, th:first-child { text-align: left; }
+svg text { font-size: 11px; font-family: sans-serif; }
+"#;
+
+/// clicking any header of a `sortable` table orders its rows by that column, numerically where
+/// the cells allow it. a second click reverses the order.
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  table.querySelectorAll("th").forEach(function (header, column) {
+    header.addEventListener("click", function () {
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      var ascending = header.dataset.order !== "asc";
+      header.dataset.order = ascending ? "asc" : "desc";
+      rows.sort(function (a, b) {
+        var x = a.cells[column].textContent, y = b.cells[column].textContent;
+        var nx = parseFloat(x), ny = parseFloat(y);
+        var order = (isNaN(nx) || isNaN(ny)) ? x.localeCompare(y) : nx - ny;
+        return ascending ? order : -order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+"#;
+
+/// escape a string for inclusion in html text or a quoted attribute.
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn color(index: usize) -> String {
+    format!("hsl({:.0}, 60%, 60%)", (index as f64 * 137.5) % 360.0)
+}
+
+fn write_table<W: Write>(writer: &mut W, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+    writeln!(writer, "<table class=\"sortable\"><thead><tr>")?;
+    for header in headers {
+        write!(writer, "<th>{}</th>", escape_html(header))?;
+    }
+    writeln!(writer, "</tr></thead><tbody>")?;
+    for row in rows {
+        write!(writer, "<tr>")?;
+        for cell in row {
+            write!(writer, "<td>{}</td>", escape_html(cell))?;
+        }
+        writeln!(writer, "</tr>")?;
+    }
+    writeln!(writer, "</tbody></table>")
+}
+
+/// pick a spacing for the time axis that yields roughly ten labelled ticks.
+fn tick_step(makespan: u32) -> u32 {
+    let mut magnitude = 1;
+    loop {
+        for factor in [1, 2, 5] {
+            let step = magnitude * factor;
+            if makespan / step <= 10 {
+                return step;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+/// draw the schedule as an svg gantt chart with one row per process.
+fn write_gantt<W: Write>(writer: &mut W, processes: &[SimProcess]) -> io::Result<()> {
+    let makespan = metrics::summarize(processes).makespan.max(1);
+    let span = (CHART_WIDTH - CHART_LABEL_WIDTH) as f64;
+    let scale = span / makespan as f64;
+    let height = processes.len() as u32 * CHART_ROW_HEIGHT + CHART_AXIS_HEIGHT;
+
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        CHART_WIDTH + 20,
+        height
+    )?;
+    for (row, process) in processes.iter().enumerate() {
+        let y = row as u32 * CHART_ROW_HEIGHT;
+        let name = escape_html(&process.name);
+        writeln!(
+            writer,
+            "<text x=\"0\" y=\"{}\">{}</text>",
+            y + CHART_ROW_HEIGHT * 2 / 3,
+            name
+        )?;
+        for burst in process.history() {
+            writeln!(
+                writer,
+                "<rect x=\"{:.2}\" y=\"{}\" width=\"{:.2}\" height=\"{}\" fill=\"{}\" stroke=\"#333\"><title>{}: {} - {} on CPU {}</title></rect>",
+                CHART_LABEL_WIDTH as f64 + burst.start as f64 * scale,
+                y + 2,
+                burst.length as f64 * scale,
+                CHART_ROW_HEIGHT - 4,
+                color(row),
+                name,
+                burst.start,
+                burst.end(),
+                burst.cpu
+            )?;
+        }
+    }
+
+    let axis = processes.len() as u32 * CHART_ROW_HEIGHT;
+    let step = tick_step(makespan);
+    let mut tick = 0;
+    while tick <= makespan {
+        let x = CHART_LABEL_WIDTH as f64 + tick as f64 * scale;
+        writeln!(
+            writer,
+            "<line x1=\"{:.2}\" y1=\"0\" x2=\"{:.2}\" y2=\"{}\" stroke=\"#ddd\"/><text x=\"{:.2}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x,
+            x,
+            axis,
+            x,
+            axis + CHART_AXIS_HEIGHT - 8,
+            tick
+        )?;
+        tick += step;
+    }
+    writeln!(writer, "</svg>")
+}
+
+/// write a self-contained html report describing a workload and the schedules produced for it.
+///
+/// the document embeds its own styles, scripts and charts so it can be viewed offline and
+/// attached anywhere as a single file.
+pub fn write_report<W: Write>(
+    writer: &mut W,
+    workload: &[SimProcess],
+    runs: &[(String, Vec<SimProcess>)],
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">"
+    )?;
+    writeln!(
+        writer,
+        "<title>Scheduler Report</title><style>{}</style></head><body>",
+        STYLE
+    )?;
+    writeln!(writer, "<h1>Scheduler Report</h1>")?;
+
+    writeln!(writer, "<h2>Workload</h2>")?;
+    let rows: Vec<Vec<String>> = workload
+        .iter()
+        .map(|p| {
+            vec![
+                p.name.clone(),
+                p.priority.to_string(),
+                p.total_burst().to_string(),
+            ]
+        })
+        .collect();
+    write_table(writer, &["Process", "Priority", "Burst"], &rows)?;
+
+    writeln!(writer, "<h2>Summary</h2>")?;
+    let rows: Vec<Vec<String>> = runs
+        .iter()
+        .map(|(label, processes)| {
+            let summary = metrics::summarize(processes);
+            vec![
+                label.clone(),
+                format!("{:.2}", summary.average_wait),
+                format!("{:.2}", summary.average_turnaround),
+                format!("{:.2}", summary.average_response),
+                summary.context_switches.to_string(),
+                summary.makespan.to_string(),
+                format!("{:.3}", summary.fairness),
+            ]
+        })
+        .collect();
+    write_table(
+        writer,
+        &[
+            "Algorithm",
+            "Avg Wait",
+            "Avg Turnaround",
+            "Avg Response",
+            "Context Switches",
+            "Makespan",
+            "Fairness",
+        ],
+        &rows,
+    )?;
+
+    for (label, processes) in runs {
+        writeln!(writer, "<h2>{}</h2>", escape_html(label))?;
+        write_gantt(writer, processes)?;
+        let rows: Vec<Vec<String>> = processes
+            .iter()
+            .map(ProcessMetrics::from)
+            .map(|m| {
+                vec![
+                    m.name,
+                    m.burst.to_string(),
+                    m.completion.to_string(),
+                    m.turnaround.to_string(),
+                    m.wait.to_string(),
+                    m.response.to_string(),
+                ]
+            })
+            .collect();
+        write_table(
+            writer,
+            &[
+                "Process",
+                "Burst",
+                "Completion",
+                "Turnaround",
+                "Wait",
+                "Response",
+            ],
+            &rows,
+        )?;
+    }
+
+    writeln!(writer, "<script>{}</script></body></html>", SCRIPT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    /// a schedule of three bursts, one of them for a process whose name needs escaping.
+    fn render() -> String {
+        let lines = ["A,1,12", "<B>,1,4"];
+        let runs = vec![(String::from("RR"), fixtures::round_robin(&lines, 8))];
+        let mut buffer: Vec<u8> = vec![];
+        write_report(&mut buffer, &fixtures::workload(&lines), &runs).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn escape_html_special_characters() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn tick_step_is_readable() {
+        assert_eq!(tick_step(8), 1);
+        assert_eq!(tick_step(20), 2);
+        assert_eq!(tick_step(100), 10);
+        assert_eq!(tick_step(270), 50);
+        assert_eq!(tick_step(1000), 100);
+    }
+
+    #[test]
+    fn report_contains_one_rect_per_burst() {
+        let document = render();
+        assert_eq!(document.matches("<rect").count(), 3);
+        assert!(document.contains("&lt;B&gt;"));
+        assert!(!document.contains("<B>"));
+    }
+
+    #[test]
+    fn report_has_no_external_assets() {
+        let document = render();
+        assert!(!document.contains("src="));
+        assert!(!document.contains("<link"));
+    }
+}
@@ -0,0 +1,165 @@
+//! turning traces of real systems into workloads.
+//!
+//! importers measure time in seconds of trace time. `scale` turns seconds into simulated ticks
+//! and `window` limits the import to part of the trace. arrivals are shifted so that the first
+//! task kept arrives at time zero.
+
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::io::BufRead;
+
+pub mod sched;
+pub mod swf;
+
+/// the kinds of trace that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// the standard workload format of the parallel workloads archive.
+    Swf,
+    /// `sched_switch` and `sched_wakeup` events dumped by ftrace or `perf script`.
+    Sched,
+}
+
+impl TraceFormat {
+    pub const ALL: [TraceFormat; 2] = [TraceFormat::Swf, TraceFormat::Sched];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            TraceFormat::Swf => "swf",
+            TraceFormat::Sched => "sched",
+        }
+    }
+
+    /// the format a trace file's extension names, if any.
+    pub fn detect(source: &str) -> Option<Self> {
+        let extension = std::path::Path::new(source).extension()?.to_str()?;
+        TraceFormat::try_from(extension.to_ascii_lowercase().as_str()).ok()
+    }
+
+    /// how many ticks a second of trace time becomes when no scale is given.
+    pub fn default_scale(&self) -> f64 {
+        match self {
+            TraceFormat::Swf => swf::DEFAULT_SCALE,
+            TraceFormat::Sched => sched::DEFAULT_SCALE,
+        }
+    }
+}
+
+impl TryFrom<&str> for TraceFormat {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        TraceFormat::ALL
+            .into_iter()
+            .find(|format| format.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
+}
+
+/// what to import from a trace, and how.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub format: TraceFormat,
+    /// simulated ticks per second of trace time, or the format's default.
+    pub scale: Option<f64>,
+    /// the seconds of trace time, from and up to, that the import is limited to.
+    pub window: Option<(f64, f64)>,
+}
+
+impl Import {
+    pub fn new(format: TraceFormat) -> Self {
+        Self {
+            format,
+            scale: None,
+            window: None,
+        }
+    }
+
+    /// whether something happening `seconds` into the trace is within the window.
+    pub fn keeps(&self, seconds: f64) -> bool {
+        self.window
+            .is_none_or(|(start, end)| (start..end).contains(&seconds))
+    }
+
+    /// a length of trace time in ticks.
+    pub fn ticks(&self, seconds: f64) -> u32 {
+        ticks(seconds, self.scale.unwrap_or(self.format.default_scale()))
+    }
+}
+
+/// `seconds` in ticks, at `scale` ticks a second.
+pub(crate) fn ticks(seconds: f64, scale: f64) -> u32 {
+    (seconds * scale).round().clamp(0.0, u32::MAX as f64) as u32
+}
+
+/// a process with bursts of cpu and i/o ticks, alternating and starting and ending with cpu.
+/// waits too short to last a tick join the bursts either side of them, and every burst lasts at
+/// least a tick.
+pub(crate) fn process(name: String, priority: u8, arrival: u32, ticks: &[u32]) -> SimProcess {
+    let mut bursts = vec![ticks[0]];
+    for pair in ticks[1..].chunks(2) {
+        match (pair[0], bursts.last_mut()) {
+            (0, Some(last)) => *last += pair[1],
+            (io, _) => bursts.extend([io, pair[1]]),
+        }
+    }
+    let mut process = SimProcess::new(name, priority, bursts[0].max(1)).with_arrival(arrival);
+    for pair in bursts[1..].chunks(2) {
+        process = process.with_io(pair[0], pair[1].max(1));
+    }
+    process
+}
+
+/// the priority, from 1 to 10, of a task with a unix nice value from -20 to 19. like nice, a
+/// smaller priority runs first: nice 0 is priority 5, every 4 steps of nice move it by one, and
+/// nice -20 and 19 are priorities 1 and 10.
+pub fn priority_of_nice(nice: i32) -> u8 {
+    (5 + (nice + 2).div_euclid(4)).clamp(1, 10) as u8
+}
+
+/// the nice value that gives a task a priority from 1 to 10, the reverse of
+/// [`priority_of_nice`].
+pub fn nice_of_priority(priority: u8) -> i32 {
+    ((priority.clamp(1, 10) as i32 - 5) * 4).min(19)
+}
+
+/// put imported processes in arrival order, the first arriving at time zero.
+pub(crate) fn rebase(processes: Vec<SimProcess>) -> Vec<SimProcess> {
+    let first = processes.iter().map(|process| process.arrival).min();
+    let mut processes: Vec<SimProcess> = processes
+        .into_iter()
+        .map(|process| {
+            let arrival = process.arrival - first.unwrap_or(0);
+            process.with_arrival(arrival)
+        })
+        .collect();
+    processes.sort_by_key(|process| process.arrival);
+    processes
+}
+
+/// import the trace read from `reader`, naming `source` in any error.
+pub fn read<R: BufRead>(source: &str, reader: R, import: &Import) -> Result<Vec<SimProcess>> {
+    match import.format {
+        TraceFormat::Swf => swf::read(source, reader, import),
+        TraceFormat::Sched => sched::read(source, reader, import),
+    }
+}
+
+/// split a line at whitespace, keeping the column each field starts at.
+pub(crate) fn split_whitespace(line: &str) -> Vec<(usize, &str)> {
+    let mut fields = vec![];
+    let mut start = None;
+    for (column, (index, c)) in line.char_indices().enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((column, index)),
+            (true, Some((first, from))) => {
+                fields.push((first, &line[from..index]));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some((first, from)) = start {
+        fields.push((first, &line[from..]));
+    }
+    fields
+}
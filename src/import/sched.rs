@@ -0,0 +1,389 @@
+//! scheduler events from ftrace or `perf script` text dumps.
+//!
+//! the importer follows `sched_switch` and `sched_wakeup` events to rebuild each task's life: the
+//! time it spends switched in is cpu time, and the time between being switched out asleep and
+//! woken up again is i/o. being preempted, switched out while still runnable, does not end a cpu
+//! burst. other lines are ignored, so a dump of every event can be read as it is. both the
+//! `key=value` fields ftrace prints and the shorter form of older `perf` are understood:
+//!
+//! ```text
+//! bash-1234 [001] d..2 5678.123456: sched_switch: prev_comm=bash prev_pid=1234 prev_prio=120 prev_state=S ==> next_comm=swapper/1 next_pid=0 next_prio=120
+//! bash 1234 [001] 5678.123456: sched:sched_switch: bash:1234 [120] S ==> swapper/1:0 [120]
+//! ```
+//!
+//! each task becomes a process named `comm-pid`, arriving when it is first seen. its priority
+//! comes from its nice value, and realtime tasks get priority 1, which runs first. the idle task
+//! is left out, as are tasks that never ran.
+
+use super::Import;
+use crate::diagnostic::Diagnostic;
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// times in the dumps are in seconds; by default a millisecond becomes a tick.
+pub const DEFAULT_SCALE: f64 = 1000.0;
+
+/// a task named in an event.
+#[derive(Debug, Clone, PartialEq)]
+struct Task {
+    comm: String,
+    pid: u32,
+    /// the kernel priority: below 100 for realtime tasks, else 120 plus the nice value.
+    prio: i32,
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    /// `prev` leaves the cpu in `state` and `next` takes it.
+    Switch {
+        prev: Task,
+        state: String,
+        next: Task,
+    },
+    Wakeup(Task),
+}
+
+/// split `key=value` fields, letting values run on over spaces until the next known key.
+fn fields<'a>(text: &str, keys: &[&'a str]) -> HashMap<&'a str, String> {
+    let mut fields: HashMap<&str, String> = HashMap::new();
+    let mut last = None;
+    for word in text.split_whitespace() {
+        let known = word.split_once('=').and_then(|(key, value)| {
+            let key = keys.iter().find(|known| **known == key)?;
+            Some((*key, value))
+        });
+        match (known, last.and_then(|key| fields.get_mut(key))) {
+            (Some((key, value)), _) => {
+                fields.insert(key, String::from(value));
+                last = Some(key);
+            }
+            (None, Some(value)) => {
+                value.push(' ');
+                value.push_str(word);
+            }
+            (None, None) => (),
+        }
+    }
+    fields
+}
+
+fn keyed_task(fields: &HashMap<&str, String>, prefix: &str) -> Option<Task> {
+    let field = |key: &str| fields.get(format!("{}{}", prefix, key).as_str());
+    Some(Task {
+        comm: field("comm")?.clone(),
+        pid: field("pid")?.parse().ok()?,
+        prio: field("prio")?.parse().ok()?,
+    })
+}
+
+/// a task written `comm:pid [prio]`, and whatever follows it.
+fn short_task(text: &str) -> Option<(Task, &str)> {
+    let open = text.rfind(" [")?;
+    let (name, rest) = text.split_at(open);
+    let (prio, rest) = rest.trim_start().strip_prefix('[')?.split_once(']')?;
+    let (comm, pid) = name.trim().rsplit_once(':')?;
+    let task = Task {
+        comm: String::from(comm),
+        pid: pid.parse().ok()?,
+        prio: prio.trim().parse().ok()?,
+    };
+    Some((task, rest.trim()))
+}
+
+/// read the fields of an event, or `None` if they are not in a form the importer knows.
+fn parse_event(name: &str, text: &str) -> Option<Event> {
+    let keyed = text.starts_with("prev_comm=") || text.starts_with("comm=");
+    match (name, keyed) {
+        ("sched_switch", true) => {
+            let keys = [
+                "prev_comm",
+                "prev_pid",
+                "prev_prio",
+                "prev_state",
+                "next_comm",
+                "next_pid",
+                "next_prio",
+            ];
+            let fields = fields(&text.replace(" ==> ", " "), &keys);
+            Some(Event::Switch {
+                prev: keyed_task(&fields, "prev_")?,
+                state: fields.get("prev_state")?.clone(),
+                next: keyed_task(&fields, "next_")?,
+            })
+        }
+        ("sched_switch", false) => {
+            let (prev, next) = text.split_once(" ==> ")?;
+            let (prev, state) = short_task(prev)?;
+            let (next, _) = short_task(next)?;
+            Some(Event::Switch {
+                prev,
+                state: String::from(state),
+                next,
+            })
+        }
+        (_, true) => {
+            let fields = fields(text, &["comm", "pid", "prio", "success", "target_cpu"]);
+            Some(Event::Wakeup(keyed_task(&fields, "")?))
+        }
+        (_, false) => Some(Event::Wakeup(short_task(text)?.0)),
+    }
+}
+
+/// the time and event on a line, `None` for lines that are not scheduler events.
+fn parse_line(line: &str) -> Option<std::result::Result<(f64, Event), String>> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (index, name) = words.iter().enumerate().find_map(|(index, word)| {
+        let name = word
+            .strip_prefix("sched:")
+            .unwrap_or(word)
+            .strip_suffix(':')?;
+        matches!(name, "sched_switch" | "sched_wakeup" | "sched_wakeup_new")
+            .then_some((index, name))
+    })?;
+    let time = index
+        .checked_sub(1)
+        .and_then(|before| words[before].strip_suffix(':')?.parse::<f64>().ok());
+    let time = match time {
+        Some(time) => time,
+        None => return Some(Err(format!("{} event has no timestamp before it", name))),
+    };
+    let (_, text) = line.split_once(words[index])?;
+    Some(
+        parse_event(name, text.trim())
+            .map(|event| (time, event))
+            .ok_or(format!("cannot read the fields of this {} event", name)),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Running(f64),
+    Ready,
+    Blocked(f64),
+    Exited,
+}
+
+/// what is known of a task so far.
+struct Thread {
+    task: Task,
+    arrival: f64,
+    /// seconds of cpu and i/o time, alternating. the last is the cpu burst under way.
+    bursts: Vec<f64>,
+    state: State,
+}
+
+impl Thread {
+    fn new(task: Task, arrival: f64) -> Self {
+        Self {
+            task,
+            arrival,
+            bursts: vec![0.0],
+            state: State::Ready,
+        }
+    }
+
+    fn wake(&mut self, time: f64) {
+        if let State::Blocked(since) = self.state {
+            self.bursts.extend([time - since, 0.0]);
+            self.state = State::Ready;
+        }
+    }
+
+    fn stop(&mut self, time: f64) {
+        if let State::Running(since) = self.state {
+            if let Some(burst) = self.bursts.last_mut() {
+                *burst += time - since;
+            }
+            self.state = State::Ready;
+        }
+    }
+
+    /// the process the task became, or `None` if it never ran.
+    fn process(self, start: f64, import: &Import) -> Option<SimProcess> {
+        if self.bursts.iter().step_by(2).all(|burst| *burst <= 0.0) {
+            return None;
+        }
+        let priority = match self.task.prio {
+            prio if prio < 100 => 1,
+            prio => super::priority_of_nice(prio - 120),
+        };
+        let name = format!("{}-{}", self.task.comm, self.task.pid);
+        let ticks: Vec<u32> = self.bursts.iter().map(|t| import.ticks(*t)).collect();
+        let arrival = import.ticks(self.arrival - start);
+        Some(super::process(name, priority, arrival, &ticks))
+    }
+}
+
+/// every task seen so far, in the order they were first seen.
+#[derive(Default)]
+struct Threads {
+    threads: Vec<Thread>,
+    /// the position of each pid in `threads`.
+    index: HashMap<u32, usize>,
+}
+
+impl Threads {
+    /// the thread of a task, first seen at `arrival` if it is new.
+    fn get(&mut self, task: &Task, arrival: f64) -> &mut Thread {
+        let position = *self.index.entry(task.pid).or_insert_with(|| {
+            self.threads.push(Thread::new(task.clone(), arrival));
+            self.threads.len() - 1
+        });
+        &mut self.threads[position]
+    }
+}
+
+/// import every task that ran in a dump of scheduler events.
+pub fn read<R: BufRead>(source: &str, reader: R, import: &Import) -> Result<Vec<SimProcess>> {
+    let mut threads = Threads::default();
+    let mut start = None;
+    let mut end = 0.0;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let (time, event) = match parse_line(&line) {
+            None => continue,
+            Some(Ok(event)) => event,
+            Some(Err(message)) => {
+                let diagnostic = Diagnostic::line(&line, message).at(source, number + 1);
+                return Err(ProgramError::InvalidWorkload(diagnostic));
+            }
+        };
+        if !import.keeps(time) {
+            continue;
+        }
+        let first = *start.get_or_insert(time);
+        end = time;
+        match event {
+            Event::Switch { prev, state, next } => {
+                if prev.pid != 0 {
+                    let unseen = !threads.index.contains_key(&prev.pid);
+                    let thread = threads.get(&prev, first);
+                    // a task switched out without being seen switched in ran from the start.
+                    if unseen {
+                        thread.state = State::Running(first);
+                    }
+                    thread.stop(time);
+                    thread.state = match state.chars().next() {
+                        Some('R') => State::Ready,
+                        Some('X' | 'Z') => State::Exited,
+                        _ => State::Blocked(time),
+                    };
+                }
+                if next.pid != 0 {
+                    let thread = threads.get(&next, time);
+                    thread.wake(time);
+                    if thread.state != State::Exited {
+                        thread.state = State::Running(time);
+                    }
+                }
+            }
+            Event::Wakeup(task) if task.pid != 0 => threads.get(&task, time).wake(time),
+            Event::Wakeup(_) => (),
+        }
+    }
+
+    let start = start.unwrap_or_default();
+    let processes = threads
+        .threads
+        .into_iter()
+        .filter_map(|mut thread| {
+            thread.stop(end);
+            thread.process(start, import)
+        })
+        .collect();
+    Ok(super::rebase(processes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NullSink;
+    use crate::import::TraceFormat;
+    use crate::{Parameters, ScheduleKind};
+
+    const FTRACE: &str = "\
+# tracer: nop
+          <idle>-0     [000] d..2 100.000000: sched_switch: prev_comm=swapper/0 prev_pid=0 prev_prio=120 prev_state=R ==> next_comm=web server next_pid=10 next_prio=120
+      web server-10    [000] d..2 100.004000: sched_switch: prev_comm=web server prev_pid=10 prev_prio=120 prev_state=S ==> next_comm=make next_pid=20 next_prio=130
+            make-20    [000] d.h3 100.010000: sched_wakeup: comm=web server pid=10 prio=120 target_cpu=000
+            make-20    [000] d..2 100.012000: sched_switch: prev_comm=make prev_pid=20 prev_prio=130 prev_state=R+ ==> next_comm=web server next_pid=10 next_prio=120
+      web server-10    [000] d..2 100.015000: sched_switch: prev_comm=web server prev_pid=10 prev_prio=120 prev_state=S ==> next_comm=make next_pid=20 next_prio=130
+            make-20    [000] d..3 100.020000: sched_waking: comm=web server pid=10 prio=120 target_cpu=000
+";
+
+    const PERF: &str = "\
+         swapper     0 [000]   100.000000: sched:sched_switch: swapper/0:0 [120] R ==> web server:10 [120]
+      web server    10 [000]   100.004000: sched:sched_switch: web server:10 [120] S ==> make:20 [130]
+            make    20 [000]   100.010000: sched:sched_wakeup: web server:10 [120] success=1 CPU:000
+            make    20 [000]   100.012000: sched:sched_switch: make:20 [130] R+ ==> web server:10 [120]
+      web server    10 [000]   100.015000: sched:sched_switch: web server:10 [120] S ==> make:20 [130]
+            make    20 [000]   100.020000: sched:sched_stat_runtime: comm=make pid=20 runtime=5000000 [ns]
+";
+
+    #[test]
+    fn rebuild_bursts_and_waits() -> Result<()> {
+        let import = Import::new(TraceFormat::Sched);
+        let processes = read("trace.txt", FTRACE.as_bytes(), &import)?;
+        let summary: Vec<(&str, u8, u32, Vec<u32>)> = processes
+            .iter()
+            .map(|p| (p.name.as_str(), p.priority, p.arrival, p.bursts()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("web server-10", 5, 0, vec![4, 6, 3]),
+                ("make-20", 8, 4, vec![8]),
+            ]
+        );
+        assert_eq!(read("perf.txt", PERF.as_bytes(), &import)?, processes);
+        Ok(())
+    }
+
+    #[test]
+    fn realtime_tasks_run_first() -> Result<()> {
+        let trace = "\
+batch-30 [000] d..2 100.000000: sched_switch: prev_comm=batch prev_pid=30 prev_prio=130 prev_state=R+ ==> next_comm=rtkit next_pid=40 next_prio=49
+rtkit-40 [000] d..2 100.002000: sched_switch: prev_comm=rtkit prev_pid=40 prev_prio=49 prev_state=S ==> next_comm=batch next_pid=30 next_prio=130
+batch-30 [000] d..2 100.010000: sched_switch: prev_comm=batch prev_pid=30 prev_prio=130 prev_state=R+ ==> next_comm=swapper/0 next_pid=0 next_prio=120
+";
+        let processes = read(
+            "trace.txt",
+            trace.as_bytes(),
+            &Import::new(TraceFormat::Sched),
+        )?;
+        let priorities: Vec<(&str, u8)> = processes
+            .iter()
+            .map(|p| (p.name.as_str(), p.priority))
+            .collect();
+        assert_eq!(priorities, vec![("batch-30", 8), ("rtkit-40", 1)]);
+
+        let parameters = Parameters::default();
+        let finished = crate::schedule(
+            ScheduleKind::Priority,
+            processes,
+            &parameters,
+            &mut NullSink,
+        );
+        let rtkit = finished.iter().find(|p| p.name == "rtkit-40").unwrap();
+        assert_eq!(rtkit.history()[0].start, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_events_without_fields() {
+        let text = "bash-1 [000] 5.0: sched_switch: prev_comm=bash\n";
+        let import = Import::new(TraceFormat::Sched);
+        match read("trace.txt", text.as_bytes(), &import) {
+            Err(ProgramError::InvalidWorkload(diagnostic)) => {
+                assert_eq!(diagnostic.line, 1);
+                assert_eq!(
+                    diagnostic.message,
+                    "cannot read the fields of this sched_switch event"
+                );
+            }
+            val => panic!("received unexpected value: {:?}", val),
+        }
+    }
+}
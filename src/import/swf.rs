@@ -0,0 +1,151 @@
+//! the standard workload format (swf) of the parallel workloads archive.
+//!
+//! each job is a line of 18 numbers separated by whitespace, with -1 for values the trace does
+//! not know. lines starting with `;` are comments. the importer reads:
+//!
+//! | field | meaning                         | becomes                                     |
+//! | ----- | ------------------------------- | ------------------------------------------- |
+//! | 1     | job number                      | the name, `J<number>`                       |
+//! | 2     | submit time                     | the arrival                                 |
+//! | 4     | run time                        | the burst                                   |
+//! | 5, 8  | allocated, requested processors | the processor count, preferring the request |
+//! | 12    | user id                         | the group, `user <id>`                      |
+//! | 15    | queue number                    | the priority, kept within 1 to 10           |
+//!
+//! jobs that never ran, with an unknown or zero run time, are left out.
+
+use super::Import;
+use crate::diagnostic::Diagnostic;
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::io::BufRead;
+
+/// times in swf traces are in seconds; by default a second becomes a tick.
+pub const DEFAULT_SCALE: f64 = 1.0;
+
+/// how many fields every job line has.
+const FIELDS: usize = 18;
+
+/// a job field the importer reads, by its position in the line starting at 1.
+fn field(line: &str, fields: &[(usize, &str)], position: usize, name: &str) -> Result<f64> {
+    let (column, text) = fields[position - 1];
+    text.parse::<f64>().map_err(|e| {
+        let message = format!("invalid {} '{}': {}", name, text, e);
+        let diagnostic = Diagnostic::new(line, column, text.chars().count(), message);
+        ProgramError::InvalidWorkload(diagnostic.with_field(name))
+    })
+}
+
+/// the job on one line, or `None` if it did not run or falls outside the window.
+fn job(line: &str, import: &Import) -> Result<Option<SimProcess>> {
+    let fields = super::split_whitespace(line);
+    if fields.len() != FIELDS {
+        let message = format!("expected {} fields but found {}", FIELDS, fields.len());
+        return Err(ProgramError::InvalidWorkload(Diagnostic::line(
+            line, message,
+        )));
+    }
+    let number = field(line, &fields, 1, "job number")?;
+    let submit = field(line, &fields, 2, "submit time")?;
+    let run = field(line, &fields, 4, "run time")?;
+    let allocated = field(line, &fields, 5, "allocated processors")?;
+    let requested = field(line, &fields, 8, "requested processors")?;
+    let user = field(line, &fields, 12, "user id")?;
+    let queue = field(line, &fields, 15, "queue number")?;
+    if run <= 0.0 || !import.keeps(submit) {
+        return Ok(None);
+    }
+
+    let priority = match queue {
+        queue if queue < 0.0 => 1,
+        queue => queue.clamp(1.0, 10.0) as u8,
+    };
+    let burst = import.ticks(run).max(1);
+    let mut process = SimProcess::new(format!("J{}", number), priority, burst)
+        .with_arrival(import.ticks(submit.max(0.0)));
+    process.processors = [requested, allocated]
+        .into_iter()
+        .find(|processors| *processors > 0.0)
+        .map(|processors| processors as u32);
+    if user >= 0.0 {
+        process.group = Some(format!("user {}", user));
+    }
+    Ok(Some(process))
+}
+
+/// import every job of an swf trace that ran and arrived within the window.
+pub fn read<R: BufRead>(source: &str, reader: R, import: &Import) -> Result<Vec<SimProcess>> {
+    let mut processes = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let text = line.trim();
+        if text.is_empty() || text.starts_with(';') {
+            continue;
+        }
+        match job(&line, import) {
+            Ok(Some(process)) => processes.push(process),
+            Ok(None) => (),
+            Err(ProgramError::InvalidWorkload(diagnostic)) => {
+                return Err(ProgramError::InvalidWorkload(
+                    diagnostic.at(source, index + 1),
+                ))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(super::rebase(processes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::TraceFormat;
+
+    const TRACE: &str = "\
+; Version: 2.2
+; MaxProcs: 128
+    1     0   10   3600   64  -1 -1   64  7200 -1 1  3  1 -1  2 1 -1 -1
+    2   120    5   -1     -1  -1 -1   32  3600 -1 5  4  1 -1  1 1 -1 -1
+    3   300    0   45     16  -1 -1   -1   600 -1 1  3  1 -1 12 1 -1 -1
+    4  4000    0   90      8  -1 -1    8   600 -1 1 -1  1 -1 -1 1 -1 -1
+";
+
+    #[test]
+    fn import_jobs_that_ran() -> Result<()> {
+        let import = Import::new(TraceFormat::Swf);
+        let processes = read("trace.swf", TRACE.as_bytes(), &import)?;
+        let names: Vec<&str> = processes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["J1", "J3", "J4"]);
+        let third = &processes[1];
+        assert_eq!((third.arrival, third.total_burst()), (300, 45));
+        assert_eq!((third.priority, third.processors), (10, Some(16)));
+        assert_eq!(third.group.as_deref(), Some("user 3"));
+        assert_eq!(
+            (processes[2].priority, processes[2].group.clone()),
+            (1, None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn scale_and_window() -> Result<()> {
+        let import = Import {
+            scale: Some(0.1),
+            window: Some((100.0, 5000.0)),
+            ..Import::new(TraceFormat::Swf)
+        };
+        let processes = read("trace.swf", TRACE.as_bytes(), &import)?;
+        let arrivals: Vec<(u32, u32)> = processes
+            .iter()
+            .map(|process| (process.arrival, process.total_burst()))
+            .collect();
+        assert_eq!(arrivals, vec![(0, 5), (370, 9)]);
+
+        let diagnostic = match read("trace.swf", "1 0 10\n".as_bytes(), &import) {
+            Err(ProgramError::InvalidWorkload(diagnostic)) => diagnostic,
+            val => panic!("received unexpected value: {:?}", val),
+        };
+        assert_eq!(diagnostic.message, "expected 18 fields but found 3");
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+//! a reader for json documents.
+//!
+//! values are read into the same tree as toml documents, so code interpreting a document does not
+//! care which of the two it was written in. objects become tables, and numbers without a fraction
+//! or exponent become integers. `null` has no counterpart: an object member that is null is left
+//! out, as if it had not been written.
+
+use crate::toml::{Table, Value};
+use crate::{ProgramError, Result};
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: String) -> Result<T> {
+        Err(ProgramError::InvalidDocument(format!(
+            "line {}: {}",
+            self.line, message
+        )))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => self.error(format!("expected '{}' but found '{}'", expected, c)),
+            None => self.error(format!("expected '{}' but the document ended", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+            self.next();
+        }
+    }
+
+    /// a value, or `None` for null.
+    fn value(&mut self) -> Result<Option<Value>> {
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Value::String(self.string()?),
+            Some(_) => return self.literal(),
+            None => return self.error(String::from("expected a value")),
+        };
+        Ok(Some(value))
+    }
+
+    fn object(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Table::default();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            if table.get(&key).is_some() {
+                return self.error(format!("duplicate key '{}'", key));
+            }
+            if let Some(value) = self.value()? {
+                table.push(key, value);
+            }
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return self.error(String::from("expected ',' or '}' in object")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Value::Array(values));
+        }
+        loop {
+            match self.value()? {
+                Some(value) => values.push(value),
+                None => return self.error(String::from("null is not allowed in arrays")),
+            }
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return self.error(String::from("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+        match u32::from_str_radix(&digits, 16) {
+            Ok(code) if digits.len() == 4 => Ok(code),
+            _ => self.error(format!("invalid escape \\u{}", digits)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex()?;
+                            // characters outside the basic plane are written as surrogate pairs.
+                            if (0xd800..0xdc00).contains(&code) {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex()?;
+                                if !(0xdc00..=0xdfff).contains(&low) {
+                                    return self.error(String::from(
+                                        "unpaired surrogate in unicode escape",
+                                    ));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            match char::from_u32(code) {
+                                Some(c) => c,
+                                None => return self.error(String::from("invalid unicode escape")),
+                            }
+                        }
+                        Some(c) => return self.error(format!("invalid escape \\{}", c)),
+                        None => return self.error(String::from("unterminated string")),
+                    };
+                    value.push(escaped);
+                }
+                Some('\n') | None => return self.error(String::from("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// `true`, `false`, `null` and numbers, which run until the next delimiter.
+    fn literal(&mut self) -> Result<Option<Value>> {
+        let mut word = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            word.push(c);
+            self.next();
+        }
+        let parsed = match word.as_str() {
+            "true" => Some(Value::Boolean(true)),
+            "false" => Some(Value::Boolean(false)),
+            "null" => return Ok(None),
+            _ if word.contains(['.', 'e', 'E']) => word.parse::<f64>().ok().map(Value::Float),
+            _ => word.parse::<i64>().ok().map(Value::Integer),
+        };
+        match parsed {
+            Some(value) => Ok(Some(value)),
+            None if word.is_empty() => match self.peek() {
+                Some(c) => self.error(format!("expected a value but found '{}'", c)),
+                None => self.error(String::from("expected a value")),
+            },
+            None => self.error(format!("invalid value '{}'", word)),
+        }
+    }
+}
+
+/// parse a whole document into its value.
+pub fn parse(text: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+    let value = match parser.value()? {
+        Some(value) => value,
+        None => return parser.error(String::from("the document is null")),
+    };
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => parser.error(format!("unexpected '{}' after the document", c)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_objects_arrays_and_values() {
+        let text = r#"{
+    "name": "convoy \"demo\" é😀",
+    "count": -12,
+    "ratio": 2.5e-1,
+    "enabled": false,
+    "missing": null,
+    "sizes": [1, [2], {"x": true}]
+}"#;
+        let document = parse(text).unwrap();
+        let root = document.as_table().unwrap();
+        assert_eq!(
+            root.get("name").unwrap().as_str(),
+            Some("convoy \"demo\" é😀")
+        );
+        assert_eq!(root.get("count").unwrap().as_integer(), Some(-12));
+        assert_eq!(root.get("ratio").unwrap().as_float(), Some(0.25));
+        assert_eq!(root.get("enabled").unwrap().as_bool(), Some(false));
+        assert!(root.get("missing").is_none());
+        let sizes = root.get("sizes").unwrap().as_array().unwrap();
+        assert_eq!(sizes[1], Value::Array(vec![Value::Integer(2)]));
+        assert_eq!(root.len(), 5);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let message = |text: &str| match parse(text) {
+            Err(ProgramError::InvalidDocument(message)) => message,
+            other => panic!("received unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            message("[1,\n 2,\n]"),
+            "line 3: expected a value but found ']'"
+        );
+        assert_eq!(
+            message("{\"a\": 1,\n\"a\": 2}"),
+            "line 2: duplicate key 'a'"
+        );
+        assert_eq!(
+            message("[1] 2"),
+            "line 1: unexpected '2' after the document"
+        );
+        assert_eq!(message("[null]"), "line 1: null is not allowed in arrays");
+        assert_eq!(
+            message("[\"\\ud800\\u0041\"]"),
+            "line 1: unpaired surrogate in unicode escape"
+        );
+    }
+}
@@ -1,9 +1,38 @@
 pub mod algo;
+pub mod cli;
+pub mod compare;
+pub mod diagnostic;
+pub mod document;
+pub mod events;
+#[cfg(test)]
+mod fixtures;
+pub mod generate;
+pub mod html;
+pub mod import;
+pub mod json;
+pub mod metrics;
+pub mod montecarlo;
+pub mod preset;
+pub mod realrun;
+pub mod record;
+pub mod repl;
+pub mod report;
+pub mod scenario;
 pub mod sim;
 pub mod structures;
+pub mod sweep;
+pub mod toml;
+pub mod trace;
+pub mod tui;
+pub mod validate;
+pub mod workload;
 
-use sim::{OrderKind, SimProcess};
-use std::io::{self, BufRead, BufReader};
+pub use cli::{Configuration, Mode};
+use diagnostic::Diagnostic;
+use document::InputFormat;
+use events::EventSink;
+use sim::{Scheduler, SimProcess, Simulation};
+use std::io::{self, BufRead, BufReader, Write};
 use std::num::ParseIntError;
 use std::{fs, result};
 
@@ -13,10 +42,32 @@ pub enum ProgramError {
     InvalidProcessSpecification(String),
     InvalidProcessParseError(ParseIntError),
     InvalidCommandInput,
+    InvalidSnapshot(String),
+    InvalidDocument(String),
+    InvalidWorkload(Diagnostic),
     GeneralError,
 }
 pub type Result<T> = result::Result<T, ProgramError>;
 
+impl std::fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::IOError(e) => write!(f, "{}", e),
+            ProgramError::InvalidProcessSpecification(value) => {
+                write!(f, "invalid process specification: {}", value)
+            }
+            ProgramError::InvalidProcessParseError(e) => write!(f, "invalid number: {}", e),
+            ProgramError::InvalidCommandInput => write!(f, "invalid command input"),
+            ProgramError::InvalidSnapshot(message) => write!(f, "invalid snapshot: {}", message),
+            ProgramError::InvalidDocument(message) => write!(f, "{}", message),
+            ProgramError::InvalidWorkload(diagnostic) => write!(f, "{}", diagnostic),
+            ProgramError::GeneralError => write!(f, "something went wrong"),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
 impl From<io::Error> for ProgramError {
     fn from(value: io::Error) -> Self {
         Self::IOError(value)
@@ -29,71 +80,220 @@ impl From<ParseIntError> for ProgramError {
     }
 }
 
-#[derive(Debug)]
+/// the length of a time quantum used by the round-robin schedulers.
+pub const DEFAULT_QUANTUM: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ScheduleKind {
     FCFS,
     SJF,
     Priority,
     RR,
     PriorityRR,
+    /// weighted fair sharing by virtual runtime, after linux's completely fair scheduler.
+    Fair,
+}
+
+impl ScheduleKind {
+    pub const ALL: [ScheduleKind; 6] = [
+        ScheduleKind::FCFS,
+        ScheduleKind::SJF,
+        ScheduleKind::Priority,
+        ScheduleKind::RR,
+        ScheduleKind::PriorityRR,
+        ScheduleKind::Fair,
+    ];
+
+    /// the name used for this scheduler on the command line.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ScheduleKind::FCFS => "fcfs",
+            ScheduleKind::SJF => "sjf",
+            ScheduleKind::Priority => "priority",
+            ScheduleKind::RR => "rr",
+            ScheduleKind::PriorityRR => "priority-rr",
+            ScheduleKind::Fair => "fair",
+        }
+    }
 }
 
-pub struct Configuration {
-    pub scheduler: ScheduleKind,
-    pub filename: String,
+impl TryFrom<&str> for ScheduleKind {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        ScheduleKind::ALL
+            .into_iter()
+            .find(|kind| kind.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
 }
 
-impl Configuration {
-    pub fn build(args: &Vec<String>) -> Result<Self> {
-        let mut iter = args.into_iter();
-        iter.next();
+/// the tunables shared by the schedulers. not every scheduler makes use of every parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parameters {
+    /// the length of a time quantum used by the round-robin schedulers.
+    pub quantum: u32,
+    /// the number of cpus taking work from the ready queue.
+    pub cpus: usize,
+    /// the time a cpu spends switching from one process to another.
+    pub overhead: u32,
+}
 
-        let in_filename = match iter.next() {
-            Some(str) => String::from(str),
-            None => {
-                eprintln!("<process-filename> is a required argument");
-                return Err(ProgramError::InvalidCommandInput);
-            }
-        };
-
-        let scheduler = match iter.next() {
-            Some(number) => match number.parse::<u8>()? {
-                0 => ScheduleKind::FCFS,
-                1 => ScheduleKind::SJF,
-                2 => ScheduleKind::Priority,
-                3 => ScheduleKind::RR,
-                4 => ScheduleKind::PriorityRR,
-                _ => return Err(ProgramError::InvalidCommandInput),
-            },
-            None => ScheduleKind::FCFS,
-        };
-
-        Ok(Self {
-            scheduler,
-            filename: in_filename,
-        })
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            quantum: DEFAULT_QUANTUM,
+            cpus: 1,
+            overhead: 0,
+        }
+    }
+}
+
+impl ScheduleKind {
+    /// build the scheduling policy for this kind.
+    pub fn scheduler(&self, parameters: &Parameters) -> Box<dyn Scheduler> {
+        match self {
+            ScheduleKind::FCFS => Box::new(algo::Fcfs::default()),
+            ScheduleKind::SJF => Box::new(algo::ShortestJobFirst::default()),
+            ScheduleKind::Priority => Box::new(algo::PriorityFirst::default()),
+            ScheduleKind::RR => Box::new(algo::RoundRobin::new(parameters.quantum)),
+            ScheduleKind::PriorityRR => Box::new(algo::PriorityRoundRobin::new(parameters.quantum)),
+            ScheduleKind::Fair => Box::new(algo::WeightedFair::new(parameters.quantum)),
+        }
+    }
+
+    /// set up a simulation of a workload under this kind of scheduler.
+    pub fn simulation(&self, processes: Vec<SimProcess>, parameters: &Parameters) -> Simulation {
+        Simulation::new(processes, self.scheduler(parameters))
+            .with_cpus(parameters.cpus)
+            .with_overhead(parameters.overhead)
     }
 }
 
-pub fn print_usage_statement(args: Vec<String>) {
-    println!("usage: {} <process-filename> <scheduler-type-id>", args[0]);
-    println!("received: {:?}", args);
+/// run a workload to completion under the given scheduler, reporting what happens to `events`.
+pub fn schedule(
+    kind: ScheduleKind,
+    processes: Vec<SimProcess>,
+    parameters: &Parameters,
+    events: &mut dyn EventSink,
+) -> Vec<SimProcess> {
+    let mut simulation = kind.simulation(processes, parameters);
+    simulation.run(events);
+    simulation.into_processes()
+}
+
+/// the workload source that stands for standard input.
+pub const STDIN: &str = "-";
+
+/// how a workload source is named in messages.
+pub fn source_name(source: &str) -> &str {
+    match source {
+        STDIN => "standard input",
+        source => source,
+    }
 }
 
-pub fn read_processes(ordering: OrderKind, filename: &str) -> Result<Vec<SimProcess>> {
-    let file = fs::File::open(filename)?;
-    let reader = BufReader::new(file);
-    let order_key = match ordering {
-        OrderKind::Burst => 0,
-        OrderKind::Priority => 1,
-    };
-    reader
-        .lines()
-        .map(|line| SimProcess::try_from(format!("{},{}", line?, order_key)))
+/// read every process of a comma-separated workload, noting `source` and the line number in any
+/// error.
+pub fn read_processes<R: BufRead>(source: &str, reader: R) -> Result<Vec<SimProcess>> {
+    let (_, lines) = workload::read_lines(source, reader)?;
+    lines
+        .into_iter()
+        .map(|line| line.process)
         .collect::<Result<Vec<SimProcess>>>()
 }
 
-pub fn display_processes(processes: &Vec<SimProcess>) {
+/// open a workload file, standard input for `-`, or the text of the preset named by a
+/// `preset:<name>` source.
+pub fn open_workload(source: &str) -> Result<Box<dyn BufRead>> {
+    if source == STDIN {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    match source.strip_prefix(preset::PREFIX) {
+        Some(name) => {
+            let mut buffer: Vec<u8> = vec![];
+            write_processes(&mut buffer, &preset::workload(name)?)?;
+            Ok(Box::new(io::Cursor::new(buffer)))
+        }
+        None => Ok(Box::new(BufReader::new(fs::File::open(source)?))),
+    }
+}
+
+/// read a workload from a file, or build the preset named by a `preset:<name>` source. the file
+/// is read in `format`, or the format its extension names.
+pub fn read_workload(source: &str, format: Option<InputFormat>) -> Result<Vec<SimProcess>> {
+    let format = format.unwrap_or_else(|| InputFormat::detect(source));
+    document::read(source_name(source), open_workload(source)?, format)
+}
+
+/// write processes in the format `read_processes` accepts. the arrival column is only written for
+/// processes that do not arrive at time zero, unless some process has i/o waits, a deadline,
+/// group, tickets, processor count or affinity; then every column is written under a header, the
+/// affinity column only when some process has one.
+pub fn write_processes<W: Write>(writer: &mut W, processes: &[SimProcess]) -> io::Result<()> {
+    let attributed = processes.iter().any(|process| {
+        process.deadline.is_some()
+            || process.group.is_some()
+            || process.tickets.is_some()
+            || process.processors.is_some()
+    });
+    let pinned = processes.iter().any(|process| !process.affinity.is_empty());
+    let phased = processes.iter().any(|process| process.total_io() > 0);
+    if attributed || phased || pinned {
+        let optional = |value: Option<u32>| value.map(|value| value.to_string());
+        writeln!(
+            writer,
+            "name, priority, {}, arrival, deadline, group, tickets, processors{}",
+            if phased { "bursts" } else { "burst" },
+            if pinned { ", affinity" } else { "" }
+        )?;
+        for process in processes {
+            let bursts: Vec<String> = process.bursts().iter().map(u32::to_string).collect();
+            let mut line = format!(
+                "{}, {}, {}, {}, {}, {}, {}, {}",
+                workload::quote(&process.name),
+                process.priority,
+                bursts.join(" "),
+                process.arrival,
+                optional(process.deadline).unwrap_or_default(),
+                process
+                    .group
+                    .as_deref()
+                    .map(workload::quote)
+                    .unwrap_or_default(),
+                optional(process.tickets).unwrap_or_default(),
+                optional(process.processors).unwrap_or_default()
+            );
+            if pinned {
+                let cpus: Vec<String> = process.affinity.iter().map(usize::to_string).collect();
+                line = format!("{}, {}", line, cpus.join(" "));
+            }
+            writeln!(writer, "{}", line.trim_end())?;
+        }
+        return Ok(());
+    }
+    for process in processes {
+        match process.arrival {
+            0 => writeln!(
+                writer,
+                "{}, {}, {}",
+                workload::quote(&process.name),
+                process.priority,
+                process.total_burst()
+            )?,
+            arrival => writeln!(
+                writer,
+                "{}, {}, {}, {}",
+                workload::quote(&process.name),
+                process.priority,
+                process.total_burst(),
+                arrival
+            )?,
+        }
+    }
+    Ok(())
+}
+
+pub fn display_processes(processes: &[SimProcess]) {
     for process in processes {
         println!("{}", process);
     }
@@ -113,8 +313,7 @@ mod tests {
         #[test]
         fn read_processes_ioerror_invalid_file() {
             let filename = "does-not-exist.txt";
-            let unnecessary_ordering = OrderKind::Burst;
-            let result = read_processes(unnecessary_ordering, filename);
+            let result = read_workload(filename, None);
             match result {
                 Err(ProgramError::IOError(_)) => (),
                 val => panic!("received unexpected value: {:?}", val),
@@ -124,15 +323,65 @@ mod tests {
         #[test]
         fn read_processes_valid_file() {
             let filename = DEFAULT_PROCESS_FILENAME;
-            let unnecessary_ordering = OrderKind::Burst;
-
-            let result = read_processes(unnecessary_ordering, filename);
+            let result = read_workload(filename, None);
             match result {
                 Ok(_) => (),
                 val => panic!("received unexpected value: {:?}", val),
             };
         }
-    }
 
-    mod config_tests {}
+        #[test]
+        fn write_processes_round_trips() -> Result<()> {
+            let processes = vec![
+                SimProcess::try_from("T1, 4, 20")?,
+                SimProcess::try_from("T2, 2, 45, 12")?,
+            ];
+            let mut buffer: Vec<u8> = vec![];
+            write_processes(&mut buffer, &processes)?;
+            let document = String::from_utf8(buffer).unwrap();
+            assert_eq!(document, "T1, 4, 20\nT2, 2, 45, 12\n");
+            let reread = read_processes("buffer", document.as_bytes())?;
+            assert_eq!(reread[1].arrival, 12);
+
+            let mut processes = processes;
+            processes[0].group = Some(String::from("ops, nightly"));
+            processes[1].processors = Some(16);
+            let mut buffer: Vec<u8> = vec![];
+            write_processes(&mut buffer, &processes)?;
+            let document = String::from_utf8(buffer).unwrap();
+            assert!(document.ends_with("\nT2, 2, 45, 12, , , , 16\n"));
+            assert_eq!(read_processes("buffer", document.as_bytes())?, processes);
+
+            processes[1] = processes[1].clone().with_io(5, 10);
+            let mut buffer: Vec<u8> = vec![];
+            write_processes(&mut buffer, &processes)?;
+            let document = String::from_utf8(buffer).unwrap();
+            assert!(document.ends_with("\nT2, 2, 45 5 10, 12, , , , 16\n"));
+            assert_eq!(read_processes("buffer", document.as_bytes())?, processes);
+
+            processes[0].affinity = vec![0, 2];
+            let mut buffer: Vec<u8> = vec![];
+            write_processes(&mut buffer, &processes)?;
+            let document = String::from_utf8(buffer).unwrap();
+            assert!(document.starts_with(
+                "name, priority, bursts, arrival, deadline, group, tickets, processors, affinity\n"
+            ));
+            assert_eq!(read_processes("buffer", document.as_bytes())?, processes);
+            Ok(())
+        }
+
+        #[test]
+        fn parse_errors_name_file_line_and_field() {
+            let text = "T1, 4, 20\nT2, 2, 4x5\n";
+            let diagnostic = match read_processes("list.txt", text.as_bytes()) {
+                Err(ProgramError::InvalidWorkload(diagnostic)) => diagnostic,
+                val => panic!("received unexpected value: {:?}", val),
+            };
+            assert_eq!(diagnostic.file, "list.txt");
+            assert_eq!(diagnostic.line, 2);
+            assert_eq!((diagnostic.column, diagnostic.length), (7, 3));
+            assert_eq!(diagnostic.field.as_deref(), Some("burst"));
+            assert_eq!(diagnostic.text, "T2, 2, 4x5");
+        }
+    }
 }
@@ -22,39 +22,285 @@ Priorities range from 1 to 10, where a higher numeric value indicates a higher r
 For round-robin scheduling, the length of a time quantum is 10 milliseconds.
  */
 
-use scheduler::{self, algo, sim, Configuration, ScheduleKind};
-fn main() -> scheduler::Result<()> {
+use scheduler::events::{EventSink, WriterSink};
+use scheduler::repl::{self, Repl};
+use scheduler::report::{self, Format};
+use scheduler::scenario::Scenario;
+use scheduler::sim::snapshot::Snapshot;
+use scheduler::sim::Simulation;
+use scheduler::{
+    self, cli, generate, html, import, montecarlo, realrun, record, sweep, trace, tui, validate,
+    Configuration, Mode, ProgramError,
+};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
     let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some(realrun::TASK) {
+        if let Err(e) = realrun::run_task(&args[2..]) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     let config = match Configuration::build(&args) {
         scheduler::Result::Ok(config) => config,
         scheduler::Result::Err(e) => {
-            eprintln!("error: {:?}", e);
-            scheduler::print_usage_statement(args);
+            // invalid command lines have already been explained.
+            if !matches!(e, ProgramError::InvalidCommandInput) {
+                eprintln!("error: {}", e);
+            }
+            eprintln!("run 'scheduler help' for usage");
             std::process::exit(1);
         }
     };
-    run(config)
+    if let Err(e) = run(config) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// where results go: the file named by --output, or standard output.
+fn output(config: &Configuration) -> io::Result<Box<dyn Write>> {
+    match &config.output {
+        Some(path) => Ok(Box::new(BufWriter::new(fs::File::create(path)?))),
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+/// the seed given with --seed, or one taken from the clock.
+fn seed(config: &Configuration) -> u64 {
+    config.seed.unwrap_or_else(|| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_nanos() as u64
+    })
+}
+
+/// report progress without mixing it into csv or json results on standard output.
+fn note(config: &Configuration, message: String) {
+    match config.format {
+        Format::Text => println!("{}", message),
+        _ => eprintln!("{}", message),
+    }
 }
 
 fn run(config: Configuration) -> scheduler::Result<()> {
-    let order = match config.scheduler {
-        ScheduleKind::Priority | ScheduleKind::PriorityRR => sim::OrderKind::Priority,
-        _ => sim::OrderKind::Burst,
+    let parameters = config.parameters;
+    match &config.mode {
+        Mode::Help(command) => {
+            print!("{}", cli::help_text(*command));
+            return Ok(());
+        }
+        Mode::Generate(generator) => {
+            let seed = seed(&config);
+            let workload = generate::generate(generator, seed)?;
+            let mut writer = output(&config)?;
+            scheduler::write_processes(&mut writer, &workload)?;
+            writer.flush()?;
+            eprintln!("generated {} processes with seed {}", workload.len(), seed);
+            return Ok(());
+        }
+        Mode::MonteCarlo(experiment) => {
+            let outcome = montecarlo::run_monte_carlo(experiment, seed(&config), &parameters)?;
+            let mut writer = output(&config)?;
+            match config.format {
+                Format::Text => write!(
+                    writer,
+                    "{}",
+                    montecarlo::monte_carlo_table(&outcome, experiment.objective)
+                )?,
+                Format::Csv => montecarlo::write_csv(&mut writer, &outcome, experiment.objective)?,
+                Format::Json => {
+                    montecarlo::write_json(&mut writer, &outcome, experiment.objective)?
+                }
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        Mode::Validate => {
+            let filename = config.filename.as_deref().unwrap_or_default();
+            let diagnostics = validate::validate_workload(filename, config.input_format)?;
+            let source = scheduler::source_name(filename);
+            for diagnostic in &diagnostics {
+                eprintln!("error: {}\n", diagnostic);
+            }
+            match diagnostics.len() {
+                0 => println!("ok: {}", source),
+                1 => eprintln!("1 problem found in {}", source),
+                count => eprintln!("{} problems found in {}", count, source),
+            }
+            if !diagnostics.is_empty() {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Mode::Import(import) => {
+            let filename = config.filename.as_deref().unwrap_or_default();
+            let source = scheduler::source_name(filename);
+            let workload = import::read(source, scheduler::open_workload(filename)?, import)?;
+            let mut writer = output(&config)?;
+            scheduler::write_processes(&mut writer, &workload)?;
+            writer.flush()?;
+            eprintln!("imported {} tasks from {}", workload.len(), source);
+            return Ok(());
+        }
+        Mode::Record(record) => {
+            let workload = record::record(record)?;
+            let mut writer = output(&config)?;
+            scheduler::write_processes(&mut writer, &workload)?;
+            writer.flush()?;
+            eprintln!("recorded {} threads", workload.len());
+            return Ok(());
+        }
+        Mode::Run => {
+            if let Some(filename) = config.filename.as_ref().filter(|f| f.ends_with(".toml")) {
+                if config.input_format.is_none() && Scenario::is_scenario(filename)? {
+                    // the scenario sets the algorithms, parameters and outputs itself.
+                    let given = config.options.iter().find(|option| **option != "workload");
+                    if let Some(option) = given {
+                        eprintln!(
+                            "error: --{} cannot be combined with a scenario file, which sets its own algorithms, parameters and outputs",
+                            option
+                        );
+                        eprintln!("run 'scheduler help run' for usage");
+                        std::process::exit(1);
+                    }
+                    return Scenario::read(filename)?.execute();
+                }
+            }
+        }
+        _ => (),
+    }
+
+    let restored = match &config.restore {
+        Some(path) => Some(Snapshot::read(BufReader::new(fs::File::open(path)?))?),
+        None => None,
+    };
+    let workload = match (&restored, &config.filename) {
+        (Some(snapshot), _) => snapshot.processes().to_vec(),
+        (None, Some(filename)) => scheduler::read_workload(filename, config.input_format)?,
+        (None, None) => vec![],
     };
+    match &config.mode {
+        Mode::RealRun(real) => {
+            println!(
+                "running {} processes for real, a tick lasting {} ms",
+                workload.len(),
+                real.tick * 1000.0
+            );
+            let measured = realrun::run(&workload, real, &parameters)?;
+            let runs: Vec<(String, Vec<scheduler::sim::SimProcess>)> = real
+                .kinds
+                .iter()
+                .map(|kind| {
+                    let processes = scheduler::schedule(
+                        *kind,
+                        workload.clone(),
+                        &parameters,
+                        &mut scheduler::events::NullSink,
+                    );
+                    (format!("{:?}", kind), processes)
+                })
+                .collect();
+            let mut writer = output(&config)?;
+            write!(writer, "{}", realrun::comparison_table(&measured, &runs))?;
+            writer.flush()?;
+            let unniced = measured.iter().filter(|m| !m.niced).count();
+            if unniced > 0 {
+                eprintln!(
+                    "note: {} processes ran at nice 0 instead of a higher priority, which needs privileges",
+                    unniced
+                );
+            }
+            return Ok(());
+        }
+        Mode::Tui => return tui::run(workload, config.scheduler, parameters),
+        Mode::Repl => {
+            println!("type 'help' for a list of commands");
+            let mut repl = Repl::new(workload, parameters);
+            return repl::run(&mut repl, io::stdin().lock(), &mut io::stdout());
+        }
+        _ => (),
+    }
+    if config.format == Format::Text {
+        println!("received: input processes");
+        match &config.mode {
+            Mode::Compare(kinds) => println!("process schedule kinds: {:?}", kinds),
+            _ => println!("process schedule kind: {:?}", config.scheduler),
+        }
+        scheduler::display_processes(&workload);
+        println!();
+    }
 
-    let processes = scheduler::read_processes(order, &config.filename)?;
-    println!("received: input processes");
-    println!("process schedule kind: {:?}", config.scheduler);
-    scheduler::display_processes(&processes);
-    println!();
-
-    let finished = match config.scheduler {
-        ScheduleKind::FCFS => algo::fcfs(processes),
-        ScheduleKind::SJF => algo::sort_before_fcfs(processes),
-        ScheduleKind::Priority => algo::sort_before_fcfs(processes),
-        ScheduleKind::RR => algo::round_robin(processes, 10),
-        ScheduleKind::PriorityRR => algo::priority_rr(processes, 10), //algo::priority_rr(processes, 10),
+    if let Mode::Sweep(sweep) = &config.mode {
+        let points = sweep::run_sweep(config.scheduler, &workload, sweep, &parameters);
+        let mut writer = output(&config)?;
+        match config.format {
+            Format::Text => write!(
+                writer,
+                "{}",
+                sweep::sweep_table(&points, sweep.parameter, sweep.objective)
+            )?,
+            Format::Csv => sweep::write_csv(&mut writer, &points, sweep.parameter)?,
+            Format::Json => report::write_sweep_json(&mut writer, &points, sweep.parameter)?,
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let kinds = match &config.mode {
+        Mode::Compare(kinds) => kinds.clone(),
+        _ => vec![config.scheduler],
     };
-    scheduler::display_processes(&finished);
+    let mut events: Vec<Box<dyn EventSink>> = vec![];
+    if config.verbosity > 0 {
+        events.push(Box::new(WriterSink::stdout(config.level())));
+    }
+    if let Some(path) = &config.log {
+        events.push(Box::new(WriterSink::file(path, config.level())?));
+    }
+
+    let mut runs = vec![];
+    for kind in kinds {
+        let mut simulation = match &restored {
+            Some(snapshot) => Simulation::restore(snapshot, kind.scheduler(&parameters)),
+            None => kind.simulation(workload.clone(), &parameters),
+        };
+        if let Some((time, path)) = &config.snapshot {
+            simulation.run_until(*time, &mut events);
+            let mut writer = BufWriter::new(fs::File::create(path)?);
+            simulation.snapshot().write(&mut writer)?;
+            note(
+                &config,
+                format!("snapshot written: {} (time {})", path, simulation.clock()),
+            );
+        }
+        simulation.run(&mut events);
+        runs.push((format!("{:?}", kind), simulation.into_processes()));
+    }
+
+    let mut writer = output(&config)?;
+    let summary = matches!(config.mode, Mode::Compare(_));
+    report::write_runs(&mut writer, &runs, config.format, summary)?;
+    writer.flush()?;
+
+    if let Some(path) = &config.trace {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        trace::write_chrome_trace(&mut writer, &runs)?;
+        note(&config, format!("trace written: {}", path));
+    }
+
+    if let Some(path) = &config.html {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        html::write_report(&mut writer, &workload, &runs)?;
+        note(&config, format!("report written: {}", path));
+    }
+    if let Some(path) = &config.output {
+        note(&config, format!("results written: {}", path));
+    }
     Ok(())
 }
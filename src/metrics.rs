@@ -0,0 +1,252 @@
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+
+/// timing measurements for a single process taken from a finished schedule.
+#[derive(Debug, PartialEq)]
+pub struct ProcessMetrics {
+    pub name: String,
+    pub burst: u32,
+    pub completion: u32,
+    pub turnaround: u32,
+    pub wait: u32,
+    pub response: u32,
+}
+
+impl From<&SimProcess> for ProcessMetrics {
+    fn from(process: &SimProcess) -> Self {
+        let history = process.history();
+        let completion = history.last().map(|burst| burst.end()).unwrap_or(0);
+        let arrival = process.arrival;
+        let response = history
+            .first()
+            .map(|burst| burst.start - arrival)
+            .unwrap_or(0);
+        Self {
+            name: process.name.clone(),
+            burst: process.total_burst(),
+            completion,
+            turnaround: completion.saturating_sub(arrival),
+            wait: process.wait,
+            response,
+        }
+    }
+}
+
+/// aggregate measurements used to compare one schedule against another.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub average_wait: f64,
+    pub average_turnaround: f64,
+    pub average_response: f64,
+    pub context_switches: u32,
+    pub makespan: u32,
+    /// jain's fairness index over each process' slowdown (turnaround relative to burst). a
+    /// value of 1 means every process was slowed down equally.
+    pub fairness: f64,
+}
+
+/// a single summary measurement that schedules can be ranked by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Wait,
+    Turnaround,
+    Response,
+    ContextSwitches,
+    Makespan,
+    Fairness,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::Wait,
+        Metric::Turnaround,
+        Metric::Response,
+        Metric::ContextSwitches,
+        Metric::Makespan,
+        Metric::Fairness,
+    ];
+
+    pub fn value(&self, summary: &Summary) -> f64 {
+        match self {
+            Metric::Wait => summary.average_wait,
+            Metric::Turnaround => summary.average_turnaround,
+            Metric::Response => summary.average_response,
+            Metric::ContextSwitches => summary.context_switches as f64,
+            Metric::Makespan => summary.makespan as f64,
+            Metric::Fairness => summary.fairness,
+        }
+    }
+
+    /// whether a smaller value of the metric indicates a better schedule.
+    pub fn prefers_lower(&self) -> bool {
+        !matches!(self, Metric::Fairness)
+    }
+
+    /// whether `a` is strictly better than `b` for this metric.
+    pub fn is_better(&self, a: f64, b: f64) -> bool {
+        match self.prefers_lower() {
+            true => a < b,
+            false => a > b,
+        }
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Metric::Wait => "Avg Wait",
+            Metric::Turnaround => "Avg Turnaround",
+            Metric::Response => "Avg Response",
+            Metric::ContextSwitches => "Context Switches",
+            Metric::Makespan => "Makespan",
+            Metric::Fairness => "Fairness",
+        }
+    }
+
+    /// the short identifier used on the command line and in csv headers.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Metric::Wait => "wait",
+            Metric::Turnaround => "turnaround",
+            Metric::Response => "response",
+            Metric::ContextSwitches => "switches",
+            Metric::Makespan => "makespan",
+            Metric::Fairness => "fairness",
+        }
+    }
+
+    /// the number of decimal places worth displaying.
+    pub fn precision(&self) -> usize {
+        match self {
+            Metric::ContextSwitches | Metric::Makespan => 0,
+            Metric::Fairness => 3,
+            _ => 2,
+        }
+    }
+}
+
+impl TryFrom<&str> for Metric {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
+}
+
+fn average(values: impl Iterator<Item = u32>, count: usize) -> f64 {
+    match count {
+        0 => 0.0,
+        _ => values.map(|value| value as f64).sum::<f64>() / count as f64,
+    }
+}
+
+/// count the number of times a cpu switched from one process to a different one.
+pub fn context_switches(processes: &[SimProcess]) -> u32 {
+    let mut dispatches: Vec<(usize, u32, usize)> = processes
+        .iter()
+        .enumerate()
+        .flat_map(|(index, process)| {
+            process
+                .history()
+                .iter()
+                .map(move |b| (b.cpu, b.start, index))
+        })
+        .collect();
+    dispatches.sort();
+    dispatches
+        .windows(2)
+        .filter(|pair| pair[0].0 == pair[1].0 && pair[0].2 != pair[1].2)
+        .count() as u32
+}
+
+/// jain's fairness index: (sum x)^2 / (n * sum x^2), ranging from 1/n up to 1.
+pub fn jain_index(values: &[f64]) -> f64 {
+    let sum: f64 = values.iter().sum();
+    let sum_of_squares: f64 = values.iter().map(|x| x * x).sum();
+    match sum_of_squares == 0.0 {
+        true => 1.0,
+        false => sum * sum / (values.len() as f64 * sum_of_squares),
+    }
+}
+
+pub fn summarize(processes: &[SimProcess]) -> Summary {
+    let metrics: Vec<ProcessMetrics> = processes.iter().map(ProcessMetrics::from).collect();
+    let count = metrics.len();
+    let slowdowns: Vec<f64> = metrics
+        .iter()
+        .filter(|m| m.burst > 0)
+        .map(|m| m.turnaround as f64 / m.burst as f64)
+        .collect();
+    Summary {
+        average_wait: average(metrics.iter().map(|m| m.wait), count),
+        average_turnaround: average(metrics.iter().map(|m| m.turnaround), count),
+        average_response: average(metrics.iter().map(|m| m.response), count),
+        context_switches: context_switches(processes),
+        makespan: metrics.iter().map(|m| m.completion).max().unwrap_or(0),
+        fairness: jain_index(&slowdowns),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    /// A is preempted at 6 for B, then finishes from 9 to 12.
+    fn build_schedule() -> Vec<SimProcess> {
+        fixtures::round_robin(&["A,1,9", "B,1,3"], 6)
+    }
+
+    #[test]
+    fn process_metrics_from_schedule() {
+        let schedule = build_schedule();
+        let a = schedule.iter().find(|p| p.name == "A").unwrap();
+        let metrics = ProcessMetrics::from(a);
+        assert_eq!(metrics.completion, 12);
+        assert_eq!(metrics.wait, 3);
+        assert_eq!(metrics.response, 0);
+    }
+
+    #[test]
+    fn summary_of_schedule() {
+        let summary = summarize(&build_schedule());
+        assert_eq!(summary.average_wait, 4.5);
+        assert_eq!(summary.average_turnaround, 10.5);
+        assert_eq!(summary.average_response, 3.0);
+        assert_eq!(summary.context_switches, 2);
+        assert_eq!(summary.makespan, 12);
+        // slowdowns of 12/9 and 9/3
+        let expected = jain_index(&[12.0 / 9.0, 3.0]);
+        assert_eq!(summary.fairness, expected);
+    }
+
+    #[test]
+    fn jain_index_bounds() {
+        assert_eq!(jain_index(&[2.0, 2.0, 2.0]), 1.0);
+        assert_eq!(jain_index(&[1.0, 0.0, 0.0, 0.0]), 0.25);
+        assert_eq!(jain_index(&[]), 1.0);
+    }
+
+    #[test]
+    fn metric_from_key() {
+        assert_eq!(
+            Metric::try_from("switches").unwrap(),
+            Metric::ContextSwitches
+        );
+        assert!(Metric::try_from("latency").is_err());
+    }
+
+    #[test]
+    fn metric_preference() {
+        assert!(Metric::Wait.is_better(1.0, 2.0));
+        assert!(Metric::Fairness.is_better(0.9, 0.5));
+        assert!(!Metric::Fairness.is_better(0.5, 0.5));
+    }
+
+    #[test]
+    fn summary_of_empty_schedule() {
+        let summary = summarize(&[]);
+        assert_eq!(summary.average_wait, 0.0);
+        assert_eq!(summary.context_switches, 0);
+    }
+}
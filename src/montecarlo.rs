@@ -0,0 +1,355 @@
+//! running schedulers over many random workloads to tell real differences from luck.
+//!
+//! every scheduler runs over the same `runs` generated workloads, one per seed. each metric is
+//! reported as its mean with a 95% confidence interval, and schedulers are compared pairwise on
+//! the per-workload differences (a paired t-test): when the interval of the difference excludes
+//! zero, the difference is significant at the 5% level.
+
+use crate::events::NullSink;
+use crate::generate::{self, Generator};
+use crate::metrics::{self, Metric, Summary};
+use crate::trace::escape_json;
+use crate::{Parameters, Result, ScheduleKind};
+use std::io::{self, Write};
+
+/// the settings of a monte carlo experiment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo {
+    pub kinds: Vec<ScheduleKind>,
+    /// how many workloads to generate.
+    pub runs: usize,
+    pub generator: Generator,
+    /// the metric schedulers are compared pairwise on.
+    pub objective: Metric,
+}
+
+impl Default for MonteCarlo {
+    fn default() -> Self {
+        Self {
+            kinds: Vec::from(ScheduleKind::ALL),
+            runs: 30,
+            generator: Generator::default(),
+            objective: Metric::Wait,
+        }
+    }
+}
+
+/// a mean and the half width of its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    pub half_width: f64,
+}
+
+impl Estimate {
+    pub fn low(&self) -> f64 {
+        self.mean - self.half_width
+    }
+
+    pub fn high(&self) -> f64 {
+        self.mean + self.half_width
+    }
+
+    /// whether the interval rules out zero, i.e. the estimate is significantly non-zero.
+    pub fn is_significant(&self) -> bool {
+        self.low() > 0.0 || self.high() < 0.0
+    }
+}
+
+/// the two-sided 95% critical value of student's t distribution. between tabulated degrees of
+/// freedom the next smaller one is used, which errs towards wider intervals.
+fn t_critical(freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match freedom {
+        0 => f64::INFINITY,
+        1..=30 => TABLE[freedom - 1],
+        31..=39 => 2.042,
+        40..=59 => 2.021,
+        60..=119 => 2.000,
+        120..=999 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// the mean of `samples` with a 95% confidence interval from the t distribution.
+pub fn estimate(samples: &[f64]) -> Estimate {
+    let count = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / count;
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    let half_width = match variance > 0.0 {
+        true => t_critical(samples.len().saturating_sub(1)) * (variance / count).sqrt(),
+        false => 0.0,
+    };
+    Estimate { mean, half_width }
+}
+
+/// the summaries of every scheduler over every generated workload.
+pub struct Outcome {
+    /// the seed of the first workload; the others follow consecutively.
+    pub seed: u64,
+    pub kinds: Vec<ScheduleKind>,
+    /// one summary per workload, for each scheduler in `kinds`.
+    pub summaries: Vec<Vec<Summary>>,
+}
+
+impl Outcome {
+    fn samples(&self, kind: usize, metric: Metric) -> Vec<f64> {
+        self.summaries[kind]
+            .iter()
+            .map(|summary| metric.value(summary))
+            .collect()
+    }
+
+    pub fn estimate(&self, kind: usize, metric: Metric) -> Estimate {
+        estimate(&self.samples(kind, metric))
+    }
+
+    /// how much larger the metric is for `first` than for `second`, paired by workload.
+    pub fn difference(&self, first: usize, second: usize, metric: Metric) -> Estimate {
+        let differences: Vec<f64> = self
+            .samples(first, metric)
+            .iter()
+            .zip(self.samples(second, metric))
+            .map(|(a, b)| a - b)
+            .collect();
+        estimate(&differences)
+    }
+
+    /// every unordered pair of schedulers, by index.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.kinds.len()).flat_map(move |a| (a + 1..self.kinds.len()).map(move |b| (a, b)))
+    }
+
+    /// which of a pair does significantly better on `metric`, if either.
+    pub fn better(&self, first: usize, second: usize, metric: Metric) -> Option<ScheduleKind> {
+        let difference = self.difference(first, second, metric);
+        match difference.is_significant() {
+            true if metric.is_better(difference.mean, 0.0) => Some(self.kinds[first]),
+            true => Some(self.kinds[second]),
+            false => None,
+        }
+    }
+}
+
+/// run every scheduler over `experiment.runs` workloads generated from `seed`, `seed + 1`, ...
+pub fn run_monte_carlo(
+    experiment: &MonteCarlo,
+    seed: u64,
+    parameters: &Parameters,
+) -> Result<Outcome> {
+    let workloads = (0..experiment.runs as u64)
+        .map(|run| generate::generate(&experiment.generator, seed.wrapping_add(run)))
+        .collect::<Result<Vec<_>>>()?;
+    let summaries = experiment
+        .kinds
+        .iter()
+        .map(|kind| {
+            workloads
+                .iter()
+                .map(|workload| {
+                    let finished =
+                        crate::schedule(*kind, workload.clone(), parameters, &mut NullSink);
+                    metrics::summarize(&finished)
+                })
+                .collect()
+        })
+        .collect();
+    Ok(Outcome {
+        seed,
+        kinds: experiment.kinds.clone(),
+        summaries,
+    })
+}
+
+fn cell(estimate: Estimate, metric: Metric) -> String {
+    format!(
+        "{:.precision$} ± {:.precision$}",
+        estimate.mean,
+        estimate.half_width,
+        precision = metric.precision().max(1)
+    )
+}
+
+/// render the estimates of every metric for every scheduler, then the pairwise comparisons on
+/// `objective`.
+pub fn monte_carlo_table(outcome: &Outcome, objective: Metric) -> String {
+    let labels: Vec<String> = outcome.kinds.iter().map(|k| format!("{:?}", k)).collect();
+    let width = labels
+        .iter()
+        .map(String::len)
+        .chain(std::iter::once("Algorithm".len()))
+        .max()
+        .unwrap_or(0);
+    let runs = outcome.summaries.first().map(Vec::len).unwrap_or(0);
+    let mut table = format!(
+        "{} workloads, seeds {} to {}, mean ± 95% confidence interval\n\n",
+        runs,
+        outcome.seed,
+        outcome.seed.wrapping_add(runs.saturating_sub(1) as u64)
+    );
+    table.push_str(&format!("{:<width$}", "Algorithm"));
+    for metric in Metric::ALL {
+        table.push_str(&format!(" | {:>20}", metric.header()));
+    }
+    table.push('\n');
+    table.push_str(&"-".repeat(width + Metric::ALL.len() * 23));
+    table.push('\n');
+    for (index, label) in labels.iter().enumerate() {
+        table.push_str(&format!("{:<width$}", label));
+        for metric in Metric::ALL {
+            let estimate = outcome.estimate(index, metric);
+            table.push_str(&format!(" | {:>20}", cell(estimate, metric)));
+        }
+        table.push('\n');
+    }
+
+    table.push_str(&format!(
+        "\npairwise differences in {} (first minus second):\n",
+        objective.key()
+    ));
+    for (a, b) in outcome.pairs() {
+        let difference = outcome.difference(a, b, objective);
+        let verdict = match outcome.better(a, b, objective) {
+            Some(kind) => format!("{:?} is better", kind),
+            None => String::from("no significant difference"),
+        };
+        table.push_str(&format!(
+            "  {:<width$} vs {:<width$} {:>20}  {}\n",
+            labels[a],
+            labels[b],
+            cell(difference, objective),
+            verdict
+        ));
+    }
+    table
+}
+
+/// write every estimate and every pairwise difference on `objective` as csv. estimates leave
+/// `versus` empty; differences are `scheduler` minus `versus`.
+pub fn write_csv<W: Write>(writer: &mut W, outcome: &Outcome, objective: Metric) -> io::Result<()> {
+    writeln!(writer, "scheduler,versus,metric,mean,low,high")?;
+    for (index, kind) in outcome.kinds.iter().enumerate() {
+        for metric in Metric::ALL {
+            let estimate = outcome.estimate(index, metric);
+            writeln!(
+                writer,
+                "{:?},,{},{},{},{}",
+                kind,
+                metric.key(),
+                estimate.mean,
+                estimate.low(),
+                estimate.high()
+            )?;
+        }
+    }
+    for (a, b) in outcome.pairs() {
+        let difference = outcome.difference(a, b, objective);
+        writeln!(
+            writer,
+            "{:?},{:?},{},{},{},{}",
+            outcome.kinds[a],
+            outcome.kinds[b],
+            objective.key(),
+            difference.mean,
+            difference.low(),
+            difference.high()
+        )?;
+    }
+    Ok(())
+}
+
+fn estimate_json(estimate: Estimate) -> String {
+    format!(
+        "{{\"mean\":{},\"low\":{},\"high\":{}}}",
+        estimate.mean,
+        estimate.low(),
+        estimate.high()
+    )
+}
+
+pub fn write_json<W: Write>(
+    writer: &mut W,
+    outcome: &Outcome,
+    objective: Metric,
+) -> io::Result<()> {
+    let schedulers: Vec<String> = outcome
+        .kinds
+        .iter()
+        .enumerate()
+        .map(|(index, kind)| {
+            let metrics: Vec<String> = Metric::ALL
+                .iter()
+                .map(|metric| {
+                    let estimate = outcome.estimate(index, *metric);
+                    format!("\"{}\":{}", metric.key(), estimate_json(estimate))
+                })
+                .collect();
+            format!(
+                "{{\"scheduler\":\"{}\",\"metrics\":{{{}}}}}",
+                escape_json(&format!("{:?}", kind)),
+                metrics.join(",")
+            )
+        })
+        .collect();
+    let pairs: Vec<String> = outcome
+        .pairs()
+        .map(|(a, b)| {
+            let better = match outcome.better(a, b, objective) {
+                Some(kind) => format!("\"{:?}\"", kind),
+                None => String::from("null"),
+            };
+            format!(
+                "{{\"first\":\"{:?}\",\"second\":\"{:?}\",\"difference\":{},\"better\":{}}}",
+                outcome.kinds[a],
+                outcome.kinds[b],
+                estimate_json(outcome.difference(a, b, objective)),
+                better
+            )
+        })
+        .collect();
+    writeln!(
+        writer,
+        "{{\"seed\":{},\"runs\":{},\"objective\":\"{}\",\"schedulers\":[{}],\"comparisons\":[{}]}}",
+        outcome.seed,
+        outcome.summaries.first().map(Vec::len).unwrap_or(0),
+        objective.key(),
+        schedulers.join(","),
+        pairs.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_matches_hand_computation() {
+        // mean 5 and sample variance 5, so the half width is t(4) * sqrt(5) / sqrt(5).
+        let estimate = estimate(&[2.0, 4.0, 5.0, 6.0, 8.0]);
+        assert_eq!(estimate.mean, 5.0);
+        assert!((estimate.half_width - 2.776).abs() < 1e-9);
+        assert!(estimate.is_significant());
+        assert!(!super::estimate(&[-1.0, 1.0, -2.0, 2.0]).is_significant());
+    }
+
+    #[test]
+    fn shortest_job_first_beats_first_come_first_served_on_wait() {
+        let experiment = MonteCarlo {
+            kinds: vec![ScheduleKind::FCFS, ScheduleKind::SJF],
+            runs: 20,
+            ..MonteCarlo::default()
+        };
+        let outcome = run_monte_carlo(&experiment, 1, &Parameters::default()).unwrap();
+        assert_eq!(outcome.summaries[1].len(), 20);
+        assert_eq!(outcome.better(0, 1, Metric::Wait), Some(ScheduleKind::SJF));
+        assert!(outcome.difference(0, 1, Metric::Wait).mean > 0.0);
+
+        let table = monte_carlo_table(&outcome, Metric::Wait);
+        assert!(table.starts_with("20 workloads, seeds 1 to 20"));
+        assert!(table.contains("SJF is better"));
+    }
+}
@@ -0,0 +1,272 @@
+//! named workloads modelling common job mixes.
+//!
+//! each preset is produced by the generator with a fixed seed, so `preset:convoy` is the same
+//! workload on every machine and in every run. anywhere a workload file is accepted,
+//! `preset:<name>` can be given instead.
+
+use crate::generate::{self, Distribution, Generator};
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+
+/// what a workload source starts with to name a preset instead of a file.
+pub const PREFIX: &str = "preset:";
+
+pub struct Preset {
+    pub name: &'static str,
+    /// what the workload models and what it is good for showing.
+    pub about: &'static str,
+    build: fn() -> Vec<SimProcess>,
+}
+
+impl Preset {
+    pub fn workload(&self) -> Vec<SimProcess> {
+        (self.build)()
+    }
+}
+
+pub const PRESETS: [Preset; 6] = [
+    Preset {
+        name: "interactive",
+        about: "many short, high priority bursts arriving close together, like an editor or shell \
+                session. response time matters most",
+        build: interactive,
+    },
+    Preset {
+        name: "batch",
+        about: "a few long, low priority jobs arriving far apart. throughput matters and \
+                preemption only adds switches",
+        build: batch,
+    },
+    Preset {
+        name: "web",
+        about: "a web server: a steady stream of short requests with the occasional slow one \
+                from a heavy-tailed mix",
+        build: web,
+    },
+    Preset {
+        name: "realtime",
+        about: "a control loop: short, top priority tasks released every 10 ticks that must not \
+                be held up",
+        build: realtime,
+    },
+    Preset {
+        name: "convoy",
+        about: "one long job arrives just before a crowd of short ones. first-come, first-served \
+                makes them all wait behind it",
+        build: convoy,
+    },
+    Preset {
+        name: "starvation",
+        about: "a low priority job competing with a stream of high priority work that arrives \
+                faster than it can be served. strict priority scheduling runs it last",
+        build: starvation,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// the workload of a `preset:<name>` source.
+pub fn workload(name: &str) -> Result<Vec<SimProcess>> {
+    match find(name) {
+        Some(preset) => Ok(preset.workload()),
+        None => {
+            let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+            Err(ProgramError::InvalidProcessSpecification(format!(
+                "unknown preset '{}', expected one of {}",
+                name,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+fn generated(generator: Generator, seed: u64) -> Vec<SimProcess> {
+    generate::generate(&generator, seed).expect("generated processes are always valid")
+}
+
+/// a process that is not part of the generated mix, arriving at `arrival`.
+fn single(name: &str, priority: u8, burst: u32, arrival: u32) -> SimProcess {
+    SimProcess::try_from(format!("{},{},{},{}", name, priority, burst, arrival).as_str())
+        .expect("preset processes are always valid")
+}
+
+fn interactive() -> Vec<SimProcess> {
+    generated(
+        Generator {
+            count: 40,
+            burst: Distribution::Exponential { mean: 4.0 },
+            priority: Distribution::Uniform {
+                low: 1.0,
+                high: 4.0,
+            },
+            interarrival: Distribution::Exponential { mean: 3.0 },
+        },
+        1,
+    )
+}
+
+fn batch() -> Vec<SimProcess> {
+    generated(
+        Generator {
+            count: 12,
+            burst: Distribution::Normal {
+                mean: 200.0,
+                deviation: 50.0,
+            },
+            priority: Distribution::Uniform {
+                low: 6.0,
+                high: 10.0,
+            },
+            interarrival: Distribution::Exponential { mean: 40.0 },
+        },
+        2,
+    )
+}
+
+fn web() -> Vec<SimProcess> {
+    generated(
+        Generator {
+            count: 60,
+            burst: Distribution::Pareto {
+                scale: 3.0,
+                shape: 1.5,
+            },
+            priority: Distribution::Normal {
+                mean: 5.0,
+                deviation: 2.0,
+            },
+            interarrival: Distribution::Exponential { mean: 5.0 },
+        },
+        3,
+    )
+}
+
+fn realtime() -> Vec<SimProcess> {
+    generated(
+        Generator {
+            count: 30,
+            burst: Distribution::Uniform {
+                low: 2.0,
+                high: 4.0,
+            },
+            priority: Distribution::Uniform {
+                low: 1.0,
+                high: 2.0,
+            },
+            interarrival: Distribution::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+        },
+        4,
+    )
+}
+
+fn convoy() -> Vec<SimProcess> {
+    let mut processes = vec![single("LONG", 5, 200, 0)];
+    processes.extend(
+        generated(
+            Generator {
+                count: 10,
+                burst: Distribution::Uniform {
+                    low: 2.0,
+                    high: 6.0,
+                },
+                priority: Distribution::Uniform {
+                    low: 5.0,
+                    high: 5.0,
+                },
+                interarrival: Distribution::Uniform {
+                    low: 0.0,
+                    high: 2.0,
+                },
+            },
+            5,
+        )
+        .into_iter()
+        .map(|process| {
+            let arrival = process.arrival + 1;
+            process.with_arrival(arrival)
+        }),
+    );
+    processes
+}
+
+fn starvation() -> Vec<SimProcess> {
+    let mut processes = generated(
+        Generator {
+            count: 40,
+            burst: Distribution::Exponential { mean: 8.0 },
+            priority: Distribution::Uniform {
+                low: 1.0,
+                high: 3.0,
+            },
+            interarrival: Distribution::Uniform {
+                low: 3.0,
+                high: 7.0,
+            },
+        },
+        6,
+    );
+    // arrive once the stream is already queueing up, keeping the workload in arrival order.
+    let index = processes
+        .iter()
+        .position(|process| process.arrival > 20)
+        .unwrap_or(processes.len());
+    processes.insert(index, single("LOW", 10, 30, 20));
+    processes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NullSink;
+    use crate::metrics::ProcessMetrics;
+    use crate::{Parameters, ScheduleKind};
+
+    fn finished(kind: ScheduleKind, name: &str) -> Vec<ProcessMetrics> {
+        let workload = workload(name).unwrap();
+        crate::schedule(kind, workload, &Parameters::default(), &mut NullSink)
+            .iter()
+            .map(ProcessMetrics::from)
+            .collect()
+    }
+
+    #[test]
+    fn presets_show_what_they_are_meant_to() {
+        // behind the long job under fcfs, short jobs wait far longer than under round-robin.
+        let wait = |kind| -> u32 {
+            finished(kind, "convoy")
+                .iter()
+                .filter(|process| process.name != "LONG")
+                .map(|process| process.wait)
+                .sum()
+        };
+        assert!(wait(ScheduleKind::FCFS) > 5 * wait(ScheduleKind::RR));
+
+        // the low priority job finishes last under priority scheduling.
+        let priority = finished(ScheduleKind::Priority, "starvation");
+        assert_eq!(priority.last().unwrap().name, "LOW");
+        let fcfs = finished(ScheduleKind::FCFS, "starvation");
+        assert!(fcfs.iter().position(|process| process.name == "LOW") < Some(10));
+    }
+
+    #[test]
+    fn every_preset_is_valid_and_stable() {
+        for preset in &PRESETS {
+            let workload = preset.workload();
+            assert!(!workload.is_empty(), "{}", preset.name);
+            assert!(workload
+                .iter()
+                .all(|process| process.total_burst() > 0 && (1..=10).contains(&process.priority)));
+            let again = preset.workload();
+            assert!(workload
+                .iter()
+                .zip(&again)
+                .all(|(a, b)| a.name == b.name && a.total_burst() == b.total_burst()));
+        }
+        assert!(workload("lottery").is_err());
+    }
+}
@@ -0,0 +1,503 @@
+//! running a workload for real and comparing it with the simulation.
+//!
+//! every process becomes a child process that sleeps until its arrival, spins on the cpu for each
+//! burst and sleeps through each i/o wait. the children are pinned to the same cpus and given nice
+//! values that follow their priorities, so the kernel's scheduler decides between them. each
+//! child measures itself: its wait is the time `/proc/self/schedstat` says it spent runnable but
+//! not running, its response the part of that before its first burst began, and its turnaround
+//! the time from its arrival until it finished.
+//!
+//! the children are this program started again with [`TASK`] as the command. this needs linux.
+
+use crate::import;
+use crate::metrics::{self, Metric};
+use crate::sim::SimProcess;
+use crate::{Parameters, ProgramError, Result, ScheduleKind};
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// the hidden command that runs one process of a real run.
+pub const TASK: &str = "realrun-task";
+
+/// how to run a workload for real.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealRun {
+    /// the schedulers to simulate alongside the real run.
+    pub kinds: Vec<ScheduleKind>,
+    /// how long a tick lasts, in seconds.
+    pub tick: f64,
+}
+
+impl Default for RealRun {
+    fn default() -> Self {
+        Self {
+            kinds: vec![ScheduleKind::Fair],
+            tick: 0.001,
+        }
+    }
+}
+
+/// the system calls a real run needs, declared directly rather than through a bindings crate.
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::ffi::{c_int, c_long, c_uint};
+    use std::io;
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: c_long,
+        tv_nsec: c_long,
+    }
+
+    extern "C" {
+        fn clock_gettime(clock: c_int, time: *mut Timespec) -> c_int;
+        fn sched_getaffinity(pid: c_int, size: usize, mask: *mut u64) -> c_int;
+        fn sched_setaffinity(pid: c_int, size: usize, mask: *const u64) -> c_int;
+        fn setpriority(which: c_int, who: c_uint, priority: c_int) -> c_int;
+    }
+
+    pub const MONOTONIC: c_int = 1;
+    pub const THREAD_CPU_TIME: c_int = 3;
+    const PRIO_PROCESS: c_int = 0;
+    /// room for 1024 cpus, as the kernel's own `cpu_set_t` has.
+    const MASK_WORDS: usize = 16;
+
+    fn check(result: c_int) -> io::Result<()> {
+        match result {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    /// nanoseconds on one of the kernel's clocks.
+    pub fn clock(clock: c_int) -> u64 {
+        let mut time = Timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // the clocks used are always available, so the call cannot fail.
+        unsafe { clock_gettime(clock, &mut time) };
+        time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+    }
+
+    /// the cpus this process may run on.
+    pub fn allowed_cpus() -> io::Result<Vec<usize>> {
+        let mut mask = [0u64; MASK_WORDS];
+        check(unsafe { sched_getaffinity(0, size_of_val(&mask), mask.as_mut_ptr()) })?;
+        Ok((0..MASK_WORDS * 64)
+            .filter(|cpu| mask[cpu / 64] & (1 << (cpu % 64)) != 0)
+            .collect())
+    }
+
+    /// keep this process to `cpus`.
+    pub fn pin(cpus: &[usize]) -> io::Result<()> {
+        let mut mask = [0u64; MASK_WORDS];
+        for cpu in cpus.iter().filter(|cpu| **cpu < MASK_WORDS * 64) {
+            mask[cpu / 64] |= 1 << (cpu % 64);
+        }
+        check(unsafe { sched_setaffinity(0, size_of_val(&mask), mask.as_ptr()) })
+    }
+
+    pub fn set_nice(nice: i32) -> io::Result<()> {
+        check(unsafe { setpriority(PRIO_PROCESS, 0, nice) })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    pub const MONOTONIC: i32 = 1;
+    pub const THREAD_CPU_TIME: i32 = 3;
+
+    fn unsupported<T>() -> io::Result<T> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "real runs need linux",
+        ))
+    }
+
+    pub fn clock(_: i32) -> u64 {
+        0
+    }
+
+    pub fn allowed_cpus() -> io::Result<Vec<usize>> {
+        unsupported()
+    }
+
+    pub fn pin(_: &[usize]) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn set_nice(_: i32) -> io::Result<()> {
+        unsupported()
+    }
+}
+
+/// the time this task has spent runnable but waiting for a cpu, in nanoseconds.
+fn run_delay() -> io::Result<u64> {
+    let text = std::fs::read_to_string("/proc/self/schedstat")?;
+    text.split_whitespace()
+        .nth(1)
+        .and_then(|delay| delay.parse().ok())
+        .ok_or_else(|| io::Error::other("cannot read /proc/self/schedstat"))
+}
+
+/// what one child is asked to do.
+#[derive(Debug, Clone, PartialEq)]
+struct Task {
+    /// when the run starts on the monotonic clock, in nanoseconds.
+    start: u64,
+    /// how long a tick lasts, in nanoseconds.
+    tick: u64,
+    cpus: Vec<usize>,
+    nice: i32,
+    arrival: u32,
+    /// cpu and i/o ticks, alternating.
+    bursts: Vec<u32>,
+}
+
+impl Task {
+    fn arguments(&self) -> Vec<String> {
+        let cpus: Vec<String> = self.cpus.iter().map(usize::to_string).collect();
+        let mut arguments = vec![
+            self.start.to_string(),
+            self.tick.to_string(),
+            cpus.join(","),
+            self.nice.to_string(),
+            self.arrival.to_string(),
+        ];
+        arguments.extend(self.bursts.iter().map(u32::to_string));
+        arguments
+    }
+
+    fn parse(arguments: &[String]) -> Option<Task> {
+        let (fixed, bursts) = arguments.split_at_checked(5)?;
+        let cpus = fixed[2]
+            .split(',')
+            .map(|cpu| cpu.parse().ok())
+            .collect::<Option<Vec<usize>>>()?;
+        Some(Task {
+            start: fixed[0].parse().ok()?,
+            tick: fixed[1].parse().ok()?,
+            cpus,
+            nice: fixed[3].parse().ok()?,
+            arrival: fixed[4].parse().ok()?,
+            bursts: bursts
+                .iter()
+                .map(|burst| burst.parse().ok())
+                .collect::<Option<Vec<u32>>>()?,
+        })
+    }
+}
+
+/// run one process of a real run, as asked by the arguments after [`TASK`], and print what it
+/// measured: its response, wait and turnaround in nanoseconds and whether it got its nice value.
+pub fn run_task(arguments: &[String]) -> Result<()> {
+    let task = Task::parse(arguments).ok_or(ProgramError::InvalidCommandInput)?;
+    sys::pin(&task.cpus)?;
+    // raising priority above the default needs privileges, so without them the task keeps it.
+    let niced = sys::set_nice(task.nice).is_ok();
+    if !niced {
+        sys::set_nice(task.nice.max(0))?;
+    }
+
+    let arrival = task.start + task.arrival as u64 * task.tick;
+    let delay = run_delay()?;
+    let now = sys::clock(sys::MONOTONIC);
+    std::thread::sleep(Duration::from_nanos(arrival.saturating_sub(now)));
+    let response = run_delay()? - delay;
+    for (index, ticks) in task.bursts.iter().enumerate() {
+        let length = *ticks as u64 * task.tick;
+        match index % 2 {
+            0 => {
+                let until = sys::clock(sys::THREAD_CPU_TIME) + length;
+                while sys::clock(sys::THREAD_CPU_TIME) < until {
+                    std::hint::spin_loop();
+                }
+            }
+            _ => std::thread::sleep(Duration::from_nanos(length)),
+        }
+    }
+    let turnaround = sys::clock(sys::MONOTONIC).saturating_sub(arrival);
+    let wait = run_delay()? - delay;
+    println!("{} {} {} {}", response, wait, turnaround, niced);
+    Ok(())
+}
+
+/// what a process measured about itself in a real run, in ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measured {
+    pub name: String,
+    pub burst: u32,
+    pub response: f64,
+    pub wait: f64,
+    pub turnaround: f64,
+    /// whether it ran at the nice value its priority asked for.
+    pub niced: bool,
+}
+
+fn parse_measurement(process: &SimProcess, tick: u64, output: &str) -> Option<Measured> {
+    let fields: Vec<&str> = output.split_whitespace().collect();
+    let ticks = |index: usize| Some(fields.get(index)?.parse::<u64>().ok()? as f64 / tick as f64);
+    Some(Measured {
+        name: process.name.clone(),
+        burst: process.total_burst(),
+        response: ticks(0)?,
+        wait: ticks(1)?,
+        turnaround: ticks(2)?,
+        niced: fields.get(3)?.parse().ok()?,
+    })
+}
+
+/// the children of a real run. any still running when they are dropped, because the run failed
+/// part way, are killed and reaped rather than left behind.
+struct Children(Vec<Child>);
+
+impl Drop for Children {
+    fn drop(&mut self) {
+        for child in &mut self.0 {
+            // children that already finished have been reaped, and killing them does nothing.
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// wait for each child in turn and collect what it measured about its process.
+fn collect(workload: &[SimProcess], mut children: Children, tick: u64) -> Result<Vec<Measured>> {
+    let mut measured = vec![];
+    for (process, child) in workload.iter().zip(&mut children.0) {
+        let mut text = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout.read_to_string(&mut text)?;
+        }
+        let status = child.wait()?;
+        match parse_measurement(process, tick, &text) {
+            Some(result) if status.success() => measured.push(result),
+            _ => {
+                return Err(ProgramError::IOError(io::Error::other(format!(
+                    "the real run of '{}' failed",
+                    process.name
+                ))))
+            }
+        }
+    }
+    Ok(measured)
+}
+
+/// run every process of a workload as a child pinned to the first `parameters.cpus` cpus this
+/// program may use, and collect what each measured.
+pub fn run(
+    workload: &[SimProcess],
+    real: &RealRun,
+    parameters: &Parameters,
+) -> Result<Vec<Measured>> {
+    let cpus: Vec<usize> = sys::allowed_cpus()?
+        .into_iter()
+        .take(parameters.cpus)
+        .collect();
+    if cpus.len() < parameters.cpus {
+        return Err(ProgramError::IOError(io::Error::other(format!(
+            "only {} cpus are available to run on",
+            cpus.len()
+        ))));
+    }
+    let program = std::env::current_exe()?;
+    let tick = (real.tick * 1e9).round().max(1.0) as u64;
+    // leave time for every child to start before the first arrival.
+    let start = sys::clock(sys::MONOTONIC) + 50_000_000 + 2_000_000 * workload.len() as u64;
+
+    let mut children = Children(vec![]);
+    for process in workload {
+        let task = Task {
+            start,
+            tick,
+            cpus: cpus.clone(),
+            nice: import::nice_of_priority(process.priority),
+            arrival: process.arrival,
+            bursts: process.bursts(),
+        };
+        let child = Command::new(&program)
+            .arg(TASK)
+            .args(task.arguments())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        children.0.push(child);
+    }
+    collect(workload, children, tick)
+}
+
+fn average(values: impl Iterator<Item = f64>, count: usize) -> f64 {
+    match count {
+        0 => 0.0,
+        count => values.sum::<f64>() / count as f64,
+    }
+}
+
+/// a table setting the real run beside the simulated ones: each process' wait and turnaround,
+/// measured and under every simulated scheduler, then the averages of every run.
+pub fn comparison_table(measured: &[Measured], runs: &[(String, Vec<SimProcess>)]) -> String {
+    let width = measured
+        .iter()
+        .map(|m| m.name.len())
+        .chain(std::iter::once("Process".len()))
+        .max()
+        .unwrap_or(0);
+    let headers: Vec<String> = ["Wait", "Turn."]
+        .into_iter()
+        .flat_map(|metric| {
+            std::iter::once(format!("{} (real)", metric)).chain(
+                runs.iter()
+                    .map(move |(label, _)| format!("{} ({})", metric, label)),
+            )
+        })
+        .collect();
+    let widths: Vec<usize> = headers.iter().map(|header| header.len().max(13)).collect();
+
+    let mut table = format!("{:<width$} | {:>6}", "Process", "Burst", width = width);
+    for (header, column) in headers.iter().zip(&widths) {
+        table.push_str(&format!(" | {:>column$}", header, column = column));
+    }
+    table.push('\n');
+    let length = width + 9 + widths.iter().map(|column| column + 3).sum::<usize>();
+    table.push_str(&"-".repeat(length));
+    table.push('\n');
+    for real in measured {
+        let simulated: Vec<Option<metrics::ProcessMetrics>> = runs
+            .iter()
+            .map(|(_, processes)| {
+                let process = processes.iter().find(|process| process.name == real.name);
+                process.map(metrics::ProcessMetrics::from)
+            })
+            .collect();
+        let cell = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+        let mut cells = vec![format!("{:.1}", real.wait)];
+        cells.extend(simulated.iter().map(|m| cell(m.as_ref().map(|m| m.wait))));
+        cells.push(format!("{:.1}", real.turnaround));
+        cells.extend(
+            simulated
+                .iter()
+                .map(|m| cell(m.as_ref().map(|m| m.turnaround))),
+        );
+
+        table.push_str(&format!(
+            "{:<width$} | {:>6}",
+            real.name,
+            real.burst,
+            width = width
+        ));
+        for (value, column) in cells.iter().zip(&widths) {
+            table.push_str(&format!(" | {:>column$}", value, column = column));
+        }
+        table.push('\n');
+    }
+
+    let columns = [Metric::Wait, Metric::Turnaround, Metric::Response];
+    let width = runs
+        .iter()
+        .map(|run| run.0.len())
+        .chain(std::iter::once("Linux (real)".len()))
+        .max()
+        .unwrap_or(0);
+    table.push_str(&format!("\n{:<width$}", "Run", width = width));
+    for column in &columns {
+        table.push_str(&format!(" | {:>16}", column.header()));
+    }
+    table.push('\n');
+    let count = measured.len();
+    let real = [
+        average(measured.iter().map(|m| m.wait), count),
+        average(measured.iter().map(|m| m.turnaround), count),
+        average(measured.iter().map(|m| m.response), count),
+    ];
+    table.push_str(&format!("{:<width$}", "Linux (real)", width = width));
+    for value in real {
+        table.push_str(&format!(" | {:>16.2}", value));
+    }
+    table.push('\n');
+    for (label, processes) in runs {
+        let summary = metrics::summarize(processes);
+        table.push_str(&format!("{:<width$}", label, width = width));
+        for column in &columns {
+            table.push_str(&format!(" | {:>16.2}", column.value(&summary)));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_round_trip_through_arguments() {
+        let task = Task {
+            start: 1_000_000,
+            tick: 1_000_000,
+            cpus: vec![2, 3],
+            nice: import::nice_of_priority(7),
+            arrival: 12,
+            bursts: vec![20, 5, 10],
+        };
+        assert_eq!(task.nice, 8);
+        assert_eq!(Task::parse(&task.arguments()), Some(task));
+        assert_eq!(
+            (import::priority_of_nice(-20), import::priority_of_nice(19)),
+            (1, 10)
+        );
+        for priority in 1..=10 {
+            let nice = import::nice_of_priority(priority);
+            assert_eq!(import::priority_of_nice(nice), priority);
+        }
+    }
+
+    #[test]
+    fn failed_task_stops_the_others() {
+        let workload = vec![
+            SimProcess::try_from("T1, 1, 10").unwrap(),
+            SimProcess::try_from("T2, 1, 10").unwrap(),
+        ];
+        let failing = Command::new("sh")
+            .args(["-c", "exit 1"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let running = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = running.id();
+        let result = collect(&workload, Children(vec![failing, running]), 1_000_000);
+        assert!(matches!(result, Err(ProgramError::IOError(_))));
+        // killed and reaped, so not even a zombie is left.
+        assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+    }
+
+    #[test]
+    fn compare_measured_and_simulated() {
+        let process = SimProcess::try_from("T1, 4, 20").unwrap();
+        let measured = parse_measurement(&process, 1000, "500 3000 23000 true").unwrap();
+        assert_eq!((measured.response, measured.wait), (0.5, 3.0));
+        assert_eq!(measured.turnaround, 23.0);
+        let parameters = Parameters::default();
+        let runs: Vec<(String, Vec<SimProcess>)> = [ScheduleKind::RR, ScheduleKind::Fair]
+            .into_iter()
+            .map(|kind| {
+                let processes = vec![process.clone()];
+                let finished =
+                    crate::schedule(kind, processes, &parameters, &mut crate::events::NullSink);
+                (format!("{:?}", kind), finished)
+            })
+            .collect();
+        let table = comparison_table(&[measured], &runs);
+        let header = table.lines().next().unwrap();
+        for column in ["Wait (RR)", "Wait (Fair)", "Turn. (RR)", "Turn. (Fair)"] {
+            assert!(header.contains(column));
+        }
+        // measured wait and turnaround, then both simulated runs of each.
+        let row = table.lines().find(|line| line.starts_with("T1 ")).unwrap();
+        let cells: Vec<&str> = row.split('|').map(str::trim).collect();
+        assert_eq!(cells, vec!["T1", "20", "3.0", "0", "0", "23.0", "20", "20"]);
+        assert!(table.contains("Linux (real)"));
+    }
+}
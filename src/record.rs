@@ -0,0 +1,374 @@
+//! recording a workload from processes running on this machine.
+//!
+//! the recorder samples `/proc/<pid>/task/<tid>/stat` for every thread of the watched processes
+//! and of their children, and tells from the change in each thread's cpu time between samples
+//! whether it ran or slept. time spent running becomes cpu bursts and time asleep between them
+//! becomes i/o, while time spent runnable without running is left for the simulator to recreate
+//! as waiting. each thread becomes a process named `comm-tid`, arriving when it first runs, with
+//! a priority that follows its nice value.
+//!
+//! the kernel counts cpu time in clock ticks, usually hundredths of a second, so bursts shorter
+//! than a tick blur into their neighbours.
+
+use crate::import;
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::collections::HashMap;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+/// the clock ticks a second `/proc` reports cpu time in, when the system cannot be asked. linux
+/// uses 100 on almost every architecture.
+const CLOCK_TICKS: f64 = 100.0;
+
+/// the clock ticks a second `/proc` reports cpu time in.
+#[cfg(target_os = "linux")]
+fn clock_ticks() -> f64 {
+    use std::ffi::{c_int, c_long};
+
+    extern "C" {
+        fn sysconf(name: c_int) -> c_long;
+    }
+    const SC_CLK_TCK: c_int = 2;
+
+    // sysconf returns -1 if it does not know the value.
+    match unsafe { sysconf(SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => CLOCK_TICKS,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clock_ticks() -> f64 {
+    CLOCK_TICKS
+}
+
+/// what to watch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// processes that are already running.
+    Pids(Vec<u32>),
+    /// a command to start, with its arguments.
+    Command(Vec<String>),
+}
+
+/// how to record a workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub target: Target,
+    /// how many seconds to record for. a command is otherwise recorded until it exits, and
+    /// running processes for ten seconds.
+    pub duration: Option<f64>,
+    /// seconds between samples.
+    pub interval: f64,
+    /// simulated ticks per second.
+    pub scale: f64,
+}
+
+impl Record {
+    pub const DEFAULT_DURATION: f64 = 10.0;
+
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            duration: None,
+            interval: 0.01,
+            scale: 1000.0,
+        }
+    }
+}
+
+/// the fields of a `stat` file the recorder reads.
+#[derive(Debug, Clone, PartialEq)]
+struct Stat {
+    comm: String,
+    /// `R` for running or runnable, `S` and `D` for asleep, and so on.
+    state: char,
+    ppid: u32,
+    /// user and system cpu time, in clock ticks.
+    cpu: u64,
+    nice: i32,
+}
+
+/// read a `stat` file. the command name is in parentheses and may itself hold spaces and
+/// parentheses, so the fields after it are found from the last closing one.
+fn parse_stat(text: &str) -> Option<Stat> {
+    let open = text.find('(')?;
+    let close = text.rfind(')')?;
+    let comm = text.get(open + 1..close)?;
+    // the fields after the name, starting with the state, which is field 3.
+    let fields: Vec<&str> = text[close + 1..].split_whitespace().collect();
+    let field = |number: usize| fields.get(number - 3).copied();
+    let utime: u64 = field(14)?.parse().ok()?;
+    let stime: u64 = field(15)?.parse().ok()?;
+    Some(Stat {
+        comm: String::from(comm),
+        state: field(3)?.chars().next()?,
+        ppid: field(4)?.parse().ok()?,
+        cpu: utime + stime,
+        nice: field(19)?.parse().ok()?,
+    })
+}
+
+fn read_stat(path: &str) -> Option<Stat> {
+    parse_stat(&std::fs::read_to_string(path).ok()?)
+}
+
+/// the numbered entries of a directory in `/proc`.
+fn numbered(path: &str) -> Vec<u32> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect()
+}
+
+/// what a thread has done so far.
+#[derive(Debug)]
+struct Thread {
+    comm: String,
+    nice: i32,
+    /// the cpu time it had used at the last sample, in clock ticks.
+    cpu: u64,
+    /// seconds into the recording that it first ran.
+    arrival: Option<f64>,
+    /// seconds of cpu and i/o time, alternating.
+    bursts: Vec<f64>,
+    asleep: bool,
+}
+
+impl Thread {
+    fn new(stat: &Stat, cpu: u64) -> Self {
+        Self {
+            comm: stat.comm.clone(),
+            nice: stat.nice,
+            cpu,
+            arrival: None,
+            bursts: vec![0.0],
+            asleep: false,
+        }
+    }
+
+    /// add seconds of cpu time, or of i/o if the thread is `asleep`.
+    fn add(&mut self, asleep: bool, seconds: f64) {
+        if self.asleep != asleep {
+            self.bursts.push(0.0);
+            self.asleep = asleep;
+        }
+        if let Some(last) = self.bursts.last_mut() {
+            *last += seconds;
+        }
+    }
+
+    /// account for the `elapsed` seconds up to `now` since the last sample, with cpu time counted
+    /// in `clock_ticks` a second.
+    fn sample(&mut self, stat: &Stat, now: f64, elapsed: f64, clock_ticks: f64) {
+        let ran = (stat.cpu.saturating_sub(self.cpu) as f64 / clock_ticks).min(elapsed);
+        (self.cpu, self.nice) = (stat.cpu, stat.nice);
+        if ran > 0.0 {
+            self.arrival.get_or_insert(now - elapsed);
+            self.add(false, ran);
+        }
+        // until it has run, time asleep only delays its arrival.
+        if matches!(stat.state, 'S' | 'D' | 'I') && self.arrival.is_some() {
+            self.add(true, elapsed - ran);
+        }
+    }
+
+    /// the process the thread became, or `None` if it never ran.
+    fn process(mut self, tid: u32, scale: f64) -> Option<SimProcess> {
+        let arrival = self.arrival?;
+        if self.asleep {
+            self.bursts.pop();
+        }
+        let ticks: Vec<u32> = self
+            .bursts
+            .iter()
+            .map(|t| import::ticks(*t, scale))
+            .collect();
+        let name = format!("{}-{}", self.comm, tid);
+        let priority = import::priority_of_nice(self.nice);
+        Some(import::process(
+            name,
+            priority,
+            import::ticks(arrival, scale),
+            &ticks,
+        ))
+    }
+}
+
+/// the threads being watched, and the processes they belong to.
+struct Recorder {
+    processes: Vec<u32>,
+    threads: HashMap<u32, Thread>,
+    /// threads in the order they were first seen.
+    order: Vec<u32>,
+    /// whether the first sample has been taken, so that threads seen from now on started during
+    /// the recording.
+    started: bool,
+    /// the clock ticks a second cpu time is counted in.
+    clock_ticks: f64,
+}
+
+impl Recorder {
+    /// add the children of watched processes, which may have been started since the last sample.
+    fn adopt(&mut self) {
+        for pid in numbered("/proc") {
+            if self.processes.contains(&pid) {
+                continue;
+            }
+            let stat = read_stat(&format!("/proc/{}/stat", pid));
+            if stat.is_some_and(|stat| self.processes.contains(&stat.ppid)) {
+                self.processes.push(pid);
+            }
+        }
+    }
+
+    /// sample every thread, returning how many could still be read.
+    fn sample(&mut self, now: f64, elapsed: f64) -> usize {
+        let mut alive = 0;
+        for pid in self.processes.clone() {
+            for tid in numbered(&format!("/proc/{}/task", pid)) {
+                let stat = match read_stat(&format!("/proc/{}/task/{}/stat", pid, tid)) {
+                    Some(stat) => stat,
+                    None => continue,
+                };
+                alive += 1;
+                match self.threads.get_mut(&tid) {
+                    Some(thread) => thread.sample(&stat, now, elapsed, self.clock_ticks),
+                    None => {
+                        // threads started during the recording have all their cpu time counted.
+                        let cpu = if self.started { 0 } else { stat.cpu };
+                        let mut thread = Thread::new(&stat, cpu);
+                        if self.started {
+                            thread.sample(&stat, now, elapsed, self.clock_ticks);
+                        }
+                        self.threads.insert(tid, thread);
+                        self.order.push(tid);
+                    }
+                }
+            }
+        }
+        self.started = true;
+        alive
+    }
+}
+
+fn spawn(command: &[String]) -> Result<Child> {
+    let (program, arguments) = command.split_first().ok_or(ProgramError::GeneralError)?;
+    Command::new(program)
+        .args(arguments)
+        .spawn()
+        .map_err(ProgramError::from)
+}
+
+/// record the target, sampling until the duration is up or everything watched has exited.
+pub fn record(record: &Record) -> Result<Vec<SimProcess>> {
+    let (mut child, processes) = match &record.target {
+        Target::Pids(pids) => (None, pids.clone()),
+        Target::Command(command) => {
+            let child = spawn(command)?;
+            let pid = child.id();
+            (Some(child), vec![pid])
+        }
+    };
+    let duration = match (record.duration, &child) {
+        (Some(duration), _) => Some(duration),
+        (None, Some(_)) => None,
+        (None, None) => Some(Record::DEFAULT_DURATION),
+    };
+    let mut recorder = Recorder {
+        processes,
+        threads: HashMap::new(),
+        order: vec![],
+        started: false,
+        clock_ticks: clock_ticks(),
+    };
+
+    let start = Instant::now();
+    let mut last = 0.0;
+    recorder.sample(0.0, 0.0);
+    loop {
+        std::thread::sleep(Duration::from_secs_f64(record.interval));
+        let now = start.elapsed().as_secs_f64();
+        recorder.adopt();
+        let alive = recorder.sample(now, now - last);
+        last = now;
+        // reap the command so that it does not linger as a zombie still being sampled.
+        let exited = match child.as_mut() {
+            Some(child) => child.try_wait()?.is_some(),
+            None => false,
+        };
+        if alive == 0 || exited || duration.is_some_and(|duration| now >= duration) {
+            break;
+        }
+    }
+    if let Some(child) = child.as_mut() {
+        if child.try_wait()?.is_none() {
+            child.kill()?;
+            child.wait()?;
+        }
+    }
+
+    let Recorder {
+        mut threads, order, ..
+    } = recorder;
+    let processes = order
+        .into_iter()
+        .filter_map(|tid| threads.remove(&tid)?.process(tid, record.scale))
+        .collect();
+    Ok(import::rebase(processes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_stat_with_odd_names() {
+        let text = "4242 (web (worker) 2) S 4200 4242 4200 0 -1 4194304 120 0 0 0 37 5 0 0 20 -5 \
+                    3 0 99 1000 200 0";
+        let stat = parse_stat(text).unwrap();
+        assert_eq!(stat.comm, "web (worker) 2");
+        assert_eq!((stat.state, stat.ppid), ('S', 4200));
+        assert_eq!((stat.cpu, stat.nice), (42, -5));
+        assert!(parse_stat("12 (short) R 1").is_none());
+    }
+
+    #[test]
+    fn clock_ticks_are_positive() {
+        assert!(clock_ticks() > 0.0);
+    }
+
+    #[test]
+    fn samples_become_bursts_and_waits() {
+        let stat = |state: char, cpu: u64| Stat {
+            comm: String::from("job"),
+            state,
+            ppid: 1,
+            cpu,
+            nice: 0,
+        };
+        let mut thread = Thread::new(&stat('S', 10), 10);
+        // asleep before it first runs, then 20ms of cpu, 20ms asleep, 10ms of cpu and a sleep.
+        let samples = [
+            ('S', 10),
+            ('R', 11),
+            ('S', 12),
+            ('S', 12),
+            ('S', 12),
+            ('R', 13),
+        ];
+        for (index, (state, cpu)) in samples.into_iter().enumerate() {
+            let now = (index + 1) as f64 * 0.01;
+            thread.sample(&stat(state, cpu), now, 0.01, CLOCK_TICKS);
+        }
+        thread.sample(&stat('S', 13), 0.07, 0.01, CLOCK_TICKS);
+        let process = thread.process(7, 1000.0).unwrap();
+        assert_eq!(process.name, "job-7");
+        assert_eq!((process.arrival, process.priority), (10, 5));
+        assert_eq!(process.bursts(), vec![20, 20, 10]);
+    }
+}
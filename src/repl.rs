@@ -0,0 +1,372 @@
+use crate::events::NullSink;
+use crate::metrics::{self, Metric, ProcessMetrics};
+use crate::sim::SimProcess;
+use crate::sweep::Parameter;
+use crate::{Parameters, ProgramError, Result, ScheduleKind};
+use std::fs;
+use std::io::{BufRead, BufWriter, Write};
+
+const GANTT_WIDTH: u32 = 60;
+const HELP: &str = "\
+commands:
+  list                                          show the workload
+  add <name> burst=<n> [priority=<n>] [arrival=<n>]
+                                                add a task to the workload
+  remove <name>                                 remove a task from the workload
+  set <parameter> <value>                       change a parameter, e.g. set quantum 5
+  params                                        show the parameters
+  run <scheduler>                               run fcfs, sjf, priority, rr, priority-rr or fair
+  metrics                                       per-task and summary metrics of the last run
+  gantt                                         gantt chart of the last run
+  history                                       show the commands entered so far
+  save <filename>                               write the workload to a file
+  load <filename>                               replace the workload with a file or preset:<name>
+  help                                          show this message
+  quit                                          end the session";
+
+/// an interactive session for building a workload and running schedulers over it.
+pub struct Repl {
+    workload: Vec<SimProcess>,
+    parameters: Parameters,
+    history: Vec<String>,
+    /// the scheduler and finished processes of the most recent run.
+    last: Option<(ScheduleKind, Vec<SimProcess>)>,
+}
+
+/// complain about a command and fail it. the message already explains the problem, so the error
+/// itself is not shown.
+fn reject<W: Write, T>(out: &mut W, message: &str) -> Result<T> {
+    writeln!(out, "{}", message)?;
+    Err(ProgramError::InvalidCommandInput)
+}
+
+impl Repl {
+    pub fn new(workload: Vec<SimProcess>, parameters: Parameters) -> Self {
+        Self {
+            workload,
+            parameters,
+            history: vec![],
+            last: None,
+        }
+    }
+
+    pub fn workload(&self) -> &[SimProcess] {
+        &self.workload
+    }
+
+    pub fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
+    /// carry out one command line, returning false when the session should end.
+    pub fn execute<W: Write>(&mut self, line: &str, out: &mut W) -> Result<bool> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(true);
+        }
+        self.history.push(String::from(line.trim()));
+        match words[..] {
+            ["quit"] | ["exit"] => return Ok(false),
+            ["help"] => writeln!(out, "{}", HELP)?,
+            ["list"] => self.list(out)?,
+            ["add", name, ref fields @ ..] => self.add(name, fields, out)?,
+            ["remove", name] => match self.workload.iter().position(|p| p.name == name) {
+                Some(index) => {
+                    self.workload.remove(index);
+                }
+                None => return reject(out, &format!("no task named '{}'", name)),
+            },
+            ["set", parameter, value] => {
+                let parameter = match Parameter::try_from(parameter) {
+                    Ok(parameter) => parameter,
+                    Err(_) => {
+                        let keys: Vec<&str> = Parameter::ALL.iter().map(|p| p.key()).collect();
+                        let message = format!(
+                            "unknown parameter '{}', expected one of {}",
+                            parameter,
+                            keys.join(", ")
+                        );
+                        return reject(out, &message);
+                    }
+                };
+                let value = value.parse::<u32>()?;
+                if value < parameter.minimum() {
+                    return reject(
+                        out,
+                        &format!(
+                            "{} must be at least {}",
+                            parameter.key(),
+                            parameter.minimum()
+                        ),
+                    );
+                }
+                parameter.apply(&mut self.parameters, value);
+            }
+            ["params"] => {
+                writeln!(out, "quantum {}", self.parameters.quantum)?;
+                writeln!(out, "cpus {}", self.parameters.cpus)?;
+                writeln!(out, "overhead {}", self.parameters.overhead)?;
+            }
+            ["run", kind] => {
+                let kind = match ScheduleKind::try_from(kind) {
+                    Ok(kind) => kind,
+                    Err(_) => {
+                        let keys: Vec<&str> = ScheduleKind::ALL.iter().map(|k| k.key()).collect();
+                        let message = format!(
+                            "unknown scheduler '{}', expected one of {}",
+                            kind,
+                            keys.join(", ")
+                        );
+                        return reject(out, &message);
+                    }
+                };
+                let finished =
+                    crate::schedule(kind, self.workload.clone(), &self.parameters, &mut NullSink);
+                self.last = Some((kind, finished));
+                self.metrics(out)?;
+            }
+            ["metrics"] => self.metrics(out)?,
+            ["gantt"] => self.gantt(out)?,
+            ["history"] => {
+                for (index, command) in self.history.iter().enumerate() {
+                    writeln!(out, "{:>4}  {}", index + 1, command)?;
+                }
+            }
+            ["save", filename] => {
+                let mut writer = BufWriter::new(fs::File::create(filename)?);
+                crate::write_processes(&mut writer, &self.workload)?;
+                writeln!(out, "saved {} tasks to {}", self.workload.len(), filename)?;
+            }
+            ["load", filename] => {
+                self.workload = crate::read_workload(filename, None)?;
+                self.last = None;
+                writeln!(
+                    out,
+                    "loaded {} tasks from {}",
+                    self.workload.len(),
+                    filename
+                )?;
+            }
+            _ => return reject(out, "unknown command, try 'help'"),
+        }
+        Ok(true)
+    }
+
+    fn list<W: Write>(&self, out: &mut W) -> Result<()> {
+        writeln!(
+            out,
+            "{:<8} {:>8} {:>8} {:>8}",
+            "name", "priority", "burst", "arrival"
+        )?;
+        for process in &self.workload {
+            writeln!(
+                out,
+                "{:<8} {:>8} {:>8} {:>8}",
+                process.name,
+                process.priority,
+                process.total_burst(),
+                process.arrival
+            )?;
+        }
+        Ok(())
+    }
+
+    fn add<W: Write>(&mut self, name: &str, fields: &[&str], out: &mut W) -> Result<()> {
+        if self.workload.iter().any(|process| process.name == name) {
+            return reject(out, &format!("a task named '{}' already exists", name));
+        }
+        let (mut priority, mut burst, mut arrival) = ("1", None, "0");
+        for field in fields {
+            match field.split_once('=') {
+                Some(("priority", value)) => priority = value,
+                Some(("burst", value)) => burst = Some(value),
+                Some(("arrival", value)) => arrival = value,
+                _ => return reject(out, &format!("unknown field '{}'", field)),
+            }
+        }
+        let burst = match burst {
+            Some(burst) => burst,
+            None => return reject(out, "a task needs a burst, e.g. burst=40"),
+        };
+        // go through the same parser as workload files so both accept exactly the same tasks.
+        let line = format!("{},{},{},{}", name, priority, burst, arrival);
+        self.workload.push(SimProcess::try_from(line.as_str())?);
+        Ok(())
+    }
+
+    fn last_run<W: Write>(&self, out: &mut W) -> Result<&(ScheduleKind, Vec<SimProcess>)> {
+        match &self.last {
+            Some(last) => Ok(last),
+            None => reject(out, "nothing has been run yet, try 'run rr'"),
+        }
+    }
+
+    fn metrics<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (kind, finished) = self.last_run(out)?;
+        writeln!(out, "{:?}", kind)?;
+        writeln!(
+            out,
+            "{:<8} {:>10} {:>10} {:>10} {:>10}",
+            "name", "completion", "turnaround", "wait", "response"
+        )?;
+        for process in finished.iter().map(ProcessMetrics::from) {
+            writeln!(
+                out,
+                "{:<8} {:>10} {:>10} {:>10} {:>10}",
+                process.name,
+                process.completion,
+                process.turnaround,
+                process.wait,
+                process.response
+            )?;
+        }
+        let summary = metrics::summarize(finished);
+        for metric in Metric::ALL {
+            writeln!(
+                out,
+                "{:<16} {:.precision$}",
+                metric.header(),
+                metric.value(&summary),
+                precision = metric.precision()
+            )?;
+        }
+        Ok(())
+    }
+
+    fn gantt<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (_, finished) = self.last_run(out)?;
+        let makespan = metrics::summarize(finished).makespan;
+        let scale = makespan.div_ceil(GANTT_WIDTH).max(1);
+        let columns = makespan.div_ceil(scale);
+        for process in finished {
+            let row: String = (0..columns)
+                .map(|column| {
+                    let (start, end) = (column * scale, (column + 1) * scale);
+                    match process
+                        .history()
+                        .iter()
+                        .any(|burst| burst.start < end && start < burst.end())
+                    {
+                        true => '#',
+                        false => ' ',
+                    }
+                })
+                .collect();
+            writeln!(out, "{:<8}|{}|", process.name, row)?;
+        }
+        writeln!(
+            out,
+            "{:<8}0{:>width$}",
+            "",
+            makespan,
+            width = columns as usize + 1
+        )?;
+        writeln!(out, "one column per {} ticks", scale)?;
+        Ok(())
+    }
+}
+
+/// read commands until the input ends or the user quits, reporting failed commands and carrying
+/// on.
+pub fn run<R: BufRead, W: Write>(repl: &mut Repl, input: R, out: &mut W) -> Result<()> {
+    let mut lines = input.lines();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        match repl.execute(&line, out) {
+            Ok(true) => (),
+            Ok(false) => return Ok(()),
+            Err(ProgramError::InvalidCommandInput) => (),
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(repl: &mut Repl, line: &str) -> (Result<bool>, String) {
+        let mut buffer: Vec<u8> = vec![];
+        let result = repl.execute(line, &mut buffer);
+        (result, String::from_utf8(buffer).unwrap())
+    }
+
+    #[test]
+    fn add_remove_and_set() {
+        let mut repl = Repl::new(vec![], Parameters::default());
+        assert!(execute(&mut repl, "add T11 priority=3 burst=40 arrival=12")
+            .0
+            .is_ok());
+        assert!(execute(&mut repl, "add T12 burst=5").0.is_ok());
+        assert_eq!(repl.workload()[0].arrival, 12);
+        assert_eq!(repl.workload()[1].priority, 1);
+
+        assert!(execute(&mut repl, "add T12 burst=5").0.is_err());
+        assert!(execute(&mut repl, "add T13 priority=2").0.is_err());
+        assert!(execute(&mut repl, "add T13 burst=x").0.is_err());
+
+        assert!(execute(&mut repl, "remove T11").0.is_ok());
+        assert!(execute(&mut repl, "remove T11").0.is_err());
+        assert_eq!(repl.workload().len(), 1);
+
+        assert!(execute(&mut repl, "set quantum 5").0.is_ok());
+        assert_eq!(repl.parameters().quantum, 5);
+        assert!(execute(&mut repl, "set quantum 0").0.is_err());
+        let (result, output) = execute(&mut repl, "set bogus 5");
+        assert!(result.is_err());
+        assert_eq!(
+            output,
+            "unknown parameter 'bogus', expected one of quantum, cpus, overhead\n"
+        );
+    }
+
+    #[test]
+    fn run_then_show_metrics_and_gantt() {
+        let mut repl = Repl::new(vec![], Parameters::default());
+        assert!(execute(&mut repl, "gantt").0.is_err());
+        execute(&mut repl, "add T1 burst=15").0.unwrap();
+        execute(&mut repl, "add T2 burst=5").0.unwrap();
+        let (result, output) = execute(&mut repl, "run rr");
+        assert!(result.unwrap());
+        assert!(output.starts_with("RR\n"));
+        assert!(output.contains("T2               15         15         10         10"));
+
+        let (_, output) = execute(&mut repl, "gantt");
+        let rows: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            rows[0],
+            format!(
+                "T2      |{}{}{}|",
+                " ".repeat(10),
+                "#".repeat(5),
+                " ".repeat(5)
+            )
+        );
+        assert_eq!(
+            rows[1],
+            format!(
+                "T1      |{}{}{}|",
+                "#".repeat(10),
+                " ".repeat(5),
+                "#".repeat(5)
+            )
+        );
+    }
+
+    #[test]
+    fn session_records_history_and_quits() {
+        let mut repl = Repl::new(vec![], Parameters::default());
+        let input = "add T1 burst=3\n\nbogus\nhistory\nquit\nlist\n";
+        let mut buffer: Vec<u8> = vec![];
+        run(&mut repl, input.as_bytes(), &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("unknown command"));
+        assert!(output.contains("   1  add T1 burst=3\n   2  bogus\n   3  history\n"));
+        assert!(!output.contains("name     priority"));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::compare;
+use crate::metrics::{self, Metric, ProcessMetrics, Summary};
+use crate::sim::SimProcess;
+use crate::sweep::{Parameter, SweepPoint};
+use crate::trace::escape_json;
+use crate::{ProgramError, Result};
+use std::io::{self, Write};
+
+/// how results are written out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// tables meant to be read by people.
+    Text,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Text, Format::Csv, Format::Json];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+}
+
+impl TryFrom<&str> for Format {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
+}
+
+/// quote a csv field if it contains anything that would break the row apart.
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => String::from(value),
+    }
+}
+
+fn summary_values(summary: &Summary) -> Vec<String> {
+    Metric::ALL
+        .iter()
+        .map(|metric| metric.value(summary).to_string())
+        .collect()
+}
+
+fn summary_json(summary: &Summary) -> String {
+    let fields: Vec<String> = Metric::ALL
+        .iter()
+        .map(|metric| format!("\"{}\":{}", metric.key(), metric.value(summary)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// write the metrics of every process of every run as csv, one row per process.
+pub fn write_process_csv<W: Write>(
+    writer: &mut W,
+    runs: &[(String, Vec<SimProcess>)],
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "scheduler,name,arrival,burst,completion,turnaround,wait,response"
+    )?;
+    for (label, processes) in runs {
+        for process in processes {
+            let metrics = ProcessMetrics::from(process);
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(label),
+                csv_field(&metrics.name),
+                process.arrival,
+                metrics.burst,
+                metrics.completion,
+                metrics.turnaround,
+                metrics.wait,
+                metrics.response
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// write the summary metrics of every run as csv, one row per run.
+pub fn write_summary_csv<W: Write>(
+    writer: &mut W,
+    runs: &[(String, Vec<SimProcess>)],
+) -> io::Result<()> {
+    let headers: Vec<&str> = Metric::ALL.iter().map(|metric| metric.key()).collect();
+    writeln!(writer, "scheduler,{}", headers.join(","))?;
+    for (label, processes) in runs {
+        let values = summary_values(&metrics::summarize(processes));
+        writeln!(writer, "{},{}", csv_field(label), values.join(","))?;
+    }
+    Ok(())
+}
+
+/// write every run, with per-process and summary metrics, as a json document.
+pub fn write_runs_json<W: Write>(
+    writer: &mut W,
+    runs: &[(String, Vec<SimProcess>)],
+) -> io::Result<()> {
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|(label, processes)| {
+            let processes_json: Vec<String> = processes
+                .iter()
+                .map(|process| {
+                    let metrics = ProcessMetrics::from(process);
+                    format!(
+                        "{{\"name\":\"{}\",\"priority\":{},\"arrival\":{},\"burst\":{},\"completion\":{},\"turnaround\":{},\"wait\":{},\"response\":{}}}",
+                        escape_json(&metrics.name),
+                        process.priority,
+                        process.arrival,
+                        metrics.burst,
+                        metrics.completion,
+                        metrics.turnaround,
+                        metrics.wait,
+                        metrics.response
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"scheduler\":\"{}\",\"summary\":{},\"processes\":[{}]}}",
+                escape_json(label),
+                summary_json(&metrics::summarize(processes)),
+                processes_json.join(",")
+            )
+        })
+        .collect();
+    writeln!(writer, "{{\"runs\":[{}]}}", runs.join(","))
+}
+
+/// write finished runs in `format`. with `summary`, text and csv show one row of summary
+/// metrics per run instead of every process.
+pub fn write_runs<W: Write>(
+    writer: &mut W,
+    runs: &[(String, Vec<SimProcess>)],
+    format: Format,
+    summary: bool,
+) -> io::Result<()> {
+    match (format, summary) {
+        (Format::Text, true) => write!(writer, "{}", compare::comparison_table(runs)),
+        (Format::Csv, true) => write_summary_csv(writer, runs),
+        (Format::Text, false) => {
+            for process in runs.iter().flat_map(|(_, processes)| processes) {
+                writeln!(writer, "{}", process)?;
+            }
+            Ok(())
+        }
+        (Format::Csv, false) => write_process_csv(writer, runs),
+        (Format::Json, _) => write_runs_json(writer, runs),
+    }
+}
+
+/// write the summary metrics of every sweep point as a json document.
+pub fn write_sweep_json<W: Write>(
+    writer: &mut W,
+    points: &[SweepPoint],
+    parameter: Parameter,
+) -> io::Result<()> {
+    let points: Vec<String> = points
+        .iter()
+        .map(|point| {
+            format!(
+                "{{\"value\":{},\"summary\":{}}}",
+                point.value,
+                summary_json(&point.summary)
+            )
+        })
+        .collect();
+    writeln!(
+        writer,
+        "{{\"parameter\":\"{}\",\"points\":[{}]}}",
+        parameter.key(),
+        points.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn build_runs() -> Vec<(String, Vec<SimProcess>)> {
+        let schedule = fixtures::round_robin(&["T1,1,15", "T2,1,5"], 10);
+        vec![(String::from("R,R"), schedule)]
+    }
+
+    #[test]
+    fn csv_rows_are_quoted() {
+        let mut buffer: Vec<u8> = vec![];
+        write_process_csv(&mut buffer, &build_runs()).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        assert_eq!(document.lines().nth(1), Some("\"R,R\",T2,0,5,15,15,10,10"));
+
+        let mut buffer: Vec<u8> = vec![];
+        write_summary_csv(&mut buffer, &build_runs()).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            document.lines().next(),
+            Some("scheduler,wait,turnaround,response,switches,makespan,fairness")
+        );
+        assert_eq!(document.lines().count(), 2);
+    }
+
+    #[test]
+    fn json_contains_summary_and_processes() {
+        let mut buffer: Vec<u8> = vec![];
+        write_runs_json(&mut buffer, &build_runs()).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        assert!(
+            document.starts_with("{\"runs\":[{\"scheduler\":\"R,R\",\"summary\":{\"wait\":7.5,")
+        );
+        assert!(document.contains("{\"name\":\"T1\",\"priority\":1,\"arrival\":0,\"burst\":15,\"completion\":20,\"turnaround\":20,\"wait\":5,\"response\":0}"));
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(Format::try_from("json").unwrap(), Format::Json);
+        assert!(Format::try_from("xml").is_err());
+    }
+}
@@ -0,0 +1,345 @@
+//! experiments described by a scenario file.
+//!
+//! a scenario names the workload, every algorithm to run over it with its parameters, and where
+//! each kind of result goes, so a whole experiment can be repeated with
+//! `scheduler run experiment.toml`:
+//!
+//! ```toml
+//! workload = "processes.txt"   # relative to the scenario file, or preset:<name>
+//! cpus = 2                     # defaults for every algorithm
+//! overhead = 1
+//!
+//! [[algorithm]]
+//! name = "fcfs"
+//!
+//! [[algorithm]]
+//! name = "rr"
+//! quantum = 5
+//! label = "RR fast"
+//!
+//! [[output]]
+//! format = "text"              # standard output
+//!
+//! [[output]]
+//! format = "html"
+//! path = "report.html"
+//! ```
+
+use crate::events::NullSink;
+use crate::report::{self, Format};
+use crate::sim::SimProcess;
+use crate::toml::{self, check_keys, number, string, tables, Table};
+use crate::{html, preset, trace, Parameters, ProgramError, Result, ScheduleKind};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// one algorithm of a scenario and the parameters it runs with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Algorithm {
+    pub kind: ScheduleKind,
+    pub label: String,
+    pub parameters: Parameters,
+}
+
+/// what an output of a scenario contains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputKind {
+    Report(Format),
+    Trace,
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub kind: OutputKind,
+    /// the file to write, or standard output.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub workload: PathBuf,
+    pub algorithms: Vec<Algorithm>,
+    pub outputs: Vec<Output>,
+}
+
+fn invalid<T>(message: String) -> Result<T> {
+    Err(ProgramError::InvalidDocument(message))
+}
+
+/// read `quantum`, `cpus` and `overhead` from a table, keeping `defaults` for those missing.
+fn parameters(table: &Table, context: &str, defaults: Parameters) -> Result<Parameters> {
+    let mut parameters = defaults;
+    if let Some(quantum) = number(table, "quantum", context, 1)? {
+        parameters.quantum = quantum;
+    }
+    if let Some(cpus) = number(table, "cpus", context, 1)? {
+        parameters.cpus = cpus as usize;
+    }
+    if let Some(overhead) = number(table, "overhead", context, 0)? {
+        parameters.overhead = overhead;
+    }
+    Ok(parameters)
+}
+
+fn algorithm(table: &Table, index: usize, defaults: Parameters) -> Result<Algorithm> {
+    let context = format!("algorithm {}", index + 1);
+    check_keys(
+        table,
+        &["name", "label", "quantum", "cpus", "overhead"],
+        &context,
+    )?;
+    let kind = match string(table, "name", &context)? {
+        Some(name) => match ScheduleKind::try_from(name) {
+            Ok(kind) => kind,
+            Err(_) => {
+                let keys: Vec<&str> = ScheduleKind::ALL.iter().map(|k| k.key()).collect();
+                return invalid(format!(
+                    "unknown algorithm '{}' in {}, expected one of {}",
+                    name,
+                    context,
+                    keys.join(", ")
+                ));
+            }
+        },
+        None => return invalid(format!("{} needs a name", context)),
+    };
+    let parameters = parameters(table, &context, defaults)?;
+    let label = match string(table, "label", &context)? {
+        Some(label) => String::from(label),
+        None => {
+            let overrides: Vec<String> = ["quantum", "cpus", "overhead"]
+                .iter()
+                .filter_map(|key| Some(format!("{} {}", key, table.get(key)?.as_integer()?)))
+                .collect();
+            match overrides.is_empty() {
+                true => format!("{:?}", kind),
+                false => format!("{:?} ({})", kind, overrides.join(", ")),
+            }
+        }
+    };
+    Ok(Algorithm {
+        kind,
+        label,
+        parameters,
+    })
+}
+
+fn output(table: &Table, index: usize, directory: &Path) -> Result<Output> {
+    let context = format!("output {}", index + 1);
+    check_keys(table, &["format", "path"], &context)?;
+    let kind = match string(table, "format", &context)? {
+        Some("trace") => OutputKind::Trace,
+        Some("html") => OutputKind::Html,
+        Some(format) => match Format::try_from(format) {
+            Ok(format) => OutputKind::Report(format),
+            Err(_) => {
+                return invalid(format!(
+                    "unknown format '{}' in {}, expected text, csv, json, trace or html",
+                    format, context
+                ))
+            }
+        },
+        None => return invalid(format!("{} needs a format", context)),
+    };
+    Ok(Output {
+        kind,
+        path: string(table, "path", &context)?.map(|path| directory.join(path)),
+    })
+}
+
+impl Scenario {
+    /// build a scenario from the text of a scenario file. relative paths are taken from
+    /// `directory`.
+    pub fn parse(text: &str, directory: &Path) -> Result<Self> {
+        let root = toml::parse(text)?;
+        check_keys(
+            &root,
+            &[
+                "workload",
+                "quantum",
+                "cpus",
+                "overhead",
+                "algorithm",
+                "output",
+            ],
+            "the scenario",
+        )?;
+        let workload = match string(&root, "workload", "the scenario")? {
+            Some(workload) if workload.starts_with(preset::PREFIX) => PathBuf::from(workload),
+            Some(workload) => directory.join(workload),
+            None => return invalid(String::from("the scenario needs a workload")),
+        };
+        let defaults = parameters(&root, "the scenario", Parameters::default())?;
+        let algorithms = tables(&root, "algorithm")?
+            .into_iter()
+            .enumerate()
+            .map(|(index, table)| algorithm(table, index, defaults))
+            .collect::<Result<Vec<Algorithm>>>()?;
+        if algorithms.is_empty() {
+            return invalid(String::from(
+                "the scenario needs at least one [[algorithm]]",
+            ));
+        }
+        let mut outputs = tables(&root, "output")?
+            .into_iter()
+            .enumerate()
+            .map(|(index, table)| output(table, index, directory))
+            .collect::<Result<Vec<Output>>>()?;
+        if outputs.is_empty() {
+            outputs.push(Output {
+                kind: OutputKind::Report(Format::Text),
+                path: None,
+            });
+        }
+        Ok(Self {
+            workload,
+            algorithms,
+            outputs,
+        })
+    }
+
+    /// whether a toml file is a scenario rather than a workload, which lists `[[task]]` tables.
+    pub fn is_scenario(filename: &str) -> Result<bool> {
+        let root = toml::parse(&fs::read_to_string(filename)?)?;
+        Ok(root.get("task").is_none())
+    }
+
+    pub fn read(filename: &str) -> Result<Self> {
+        let path = Path::new(filename);
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&fs::read_to_string(path)?, directory)
+    }
+
+    /// run every algorithm over `workload`, labelling each run.
+    pub fn run(&self, workload: &[SimProcess]) -> Vec<(String, Vec<SimProcess>)> {
+        self.algorithms
+            .iter()
+            .map(|algorithm| {
+                let finished = crate::schedule(
+                    algorithm.kind,
+                    workload.to_vec(),
+                    &algorithm.parameters,
+                    &mut NullSink,
+                );
+                (algorithm.label.clone(), finished)
+            })
+            .collect()
+    }
+
+    /// read the workload, run every algorithm and write every output.
+    pub fn execute(&self) -> Result<()> {
+        let filename = self.workload.to_string_lossy();
+        let workload = crate::read_workload(&filename, None)?;
+        let runs = self.run(&workload);
+        for output in &self.outputs {
+            let mut writer: Box<dyn Write> = match &output.path {
+                Some(path) => Box::new(BufWriter::new(fs::File::create(path)?)),
+                None => Box::new(io::stdout()),
+            };
+            match output.kind {
+                OutputKind::Report(format) => {
+                    report::write_runs(&mut writer, &runs, format, runs.len() > 1)?
+                }
+                OutputKind::Trace => trace::write_chrome_trace(&mut writer, &runs)?,
+                OutputKind::Html => html::write_report(&mut writer, &workload, &runs)?,
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = "\
+workload = \"processes.txt\"
+cpus = 2
+
+[[algorithm]]
+name = \"fcfs\"
+
+[[algorithm]]
+name = \"rr\"
+quantum = 5
+
+[[algorithm]]
+name = \"rr\"
+label = \"RR slow\"
+quantum = 20
+overhead = 1
+
+[[output]]
+format = \"csv\"
+path = \"out/results.csv\"
+
+[[output]]
+format = \"trace\"
+";
+
+    #[test]
+    fn parse_scenario() {
+        let scenario = Scenario::parse(SCENARIO, Path::new("experiments")).unwrap();
+        assert_eq!(scenario.workload, Path::new("experiments/processes.txt"));
+        let labels: Vec<&str> = scenario
+            .algorithms
+            .iter()
+            .map(|algorithm| algorithm.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["FCFS", "RR (quantum 5)", "RR slow"]);
+        let slow = &scenario.algorithms[2];
+        assert_eq!(slow.kind, ScheduleKind::RR);
+        assert_eq!(
+            slow.parameters,
+            Parameters {
+                quantum: 20,
+                cpus: 2,
+                overhead: 1
+            }
+        );
+        assert_eq!(
+            scenario.outputs,
+            vec![
+                Output {
+                    kind: OutputKind::Report(Format::Csv),
+                    path: Some(PathBuf::from("experiments/out/results.csv")),
+                },
+                Output {
+                    kind: OutputKind::Trace,
+                    path: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_incomplete_or_misspelled_scenarios() {
+        let message = |text: &str| match Scenario::parse(text, Path::new("")) {
+            Err(ProgramError::InvalidDocument(message)) => message,
+            other => panic!("received unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            message("[[algorithm]]\nname = \"rr\"\n"),
+            "the scenario needs a workload"
+        );
+        assert_eq!(
+            message("workload = \"a\"\n"),
+            "the scenario needs at least one [[algorithm]]"
+        );
+        assert!(
+            message("workload = \"a\"\n[[algorithm]]\nname = \"lottery\"\n")
+                .starts_with("unknown algorithm 'lottery' in algorithm 1")
+        );
+        assert!(
+            message("workload = \"a\"\n[[algorithm]]\nname = \"rr\"\nquantom = 5\n")
+                .starts_with("unknown key 'quantom' in algorithm 1")
+        );
+        assert_eq!(
+            message("workload = \"a\"\ncpus = 0\n[[algorithm]]\nname = \"rr\"\n"),
+            "'cpus' in the scenario should be at least 1, not 0"
+        );
+    }
+}
@@ -1,177 +1,727 @@
+use crate::events::{Event, EventKind, EventSink};
 use crate::{ProgramError, Result};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
 
-#[derive(Debug)]
-pub enum OrderKind {
-    Burst,
-    Priority,
+pub mod snapshot;
+
+/// a single uninterrupted stretch of cpu time given to a process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Burst {
+    pub start: u32,
+    pub length: u32,
+    /// the cpu the process ran on.
+    pub cpu: usize,
+    /// whether the process went on to wait for i/o when the burst ended.
+    pub blocked: bool,
 }
 
-impl std::fmt::Display for OrderKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                OrderKind::Burst => "Burst",
-                OrderKind::Priority => "Priority",
-            }
-        )
+impl Burst {
+    pub fn end(&self) -> u32 {
+        self.start + self.length
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SimProcess {
     pub name: String,
     pub priority: u8,
+    /// the time the process first becomes ready to run.
+    pub arrival: u32,
+    /// the time the process should be finished by, if it has one.
+    pub deadline: Option<u32>,
+    /// the user, service or job the process belongs to, if any.
+    pub group: Option<String>,
+    /// the process' share of the cpu under lottery scheduling, if it was given one.
+    pub tickets: Option<u32>,
+    /// the cpus the process may run on, or empty if it may run on any.
+    pub affinity: Vec<usize>,
+    /// how many cpus a parallel job asked for. the simulator runs every process on one.
+    pub processors: Option<u32>,
+    /// the cpu time left in the current burst.
     pub remaining_burst: u32,
     running_time: u32,
     pub wait: u32,
-    order: OrderKind,
+    burst: u32,
+    io: u32,
+    /// the bursts still to come after the current one, each preceded by an i/o wait.
+    pending: VecDeque<(u32, u32)>,
+    ready_since: u32,
+    history: Vec<Burst>,
 }
 
 impl std::fmt::Display for SimProcess {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Process: {:06} | Priority: {:06} | Running Time: {:06} | Remaining Burst: {:06} | Wait Time: {:06} | Order: {:06}",
-            self.name, self.priority, self.running_time, self.remaining_burst, self.wait, self.order,
+            "Process: {:06} | Priority: {:06} | Running Time: {:06} | Remaining Burst: {:06} | Wait Time: {:06}",
+            self.name, self.priority, self.running_time, self.remaining_burst, self.wait,
         )
     }
 }
 
-impl TryFrom<String> for SimProcess {
+impl TryFrom<&str> for SimProcess {
     type Error = ProgramError;
-    fn try_from(value: String) -> Result<Self> {
-        let mut components = value.split(",").map(|s| String::from(s.trim()));
-        let name = match components.next() {
-            Some(str) => str,
-            _ => {
-                return Err(ProgramError::InvalidProcessSpecification(String::from(
-                    value,
-                )))
-            }
+    /// parse one line of a workload file: `name, priority, burst[, arrival]`.
+    fn try_from(value: &str) -> Result<Self> {
+        crate::workload::Layout::POSITIONAL.parse(value)
+    }
+}
+
+impl SimProcess {
+    pub(crate) fn new(name: String, priority: u8, burst: u32) -> Self {
+        Self {
+            name,
+            priority,
+            remaining_burst: burst,
+            wait: 0,
+            running_time: 0,
+            arrival: 0,
+            deadline: None,
+            group: None,
+            tickets: None,
+            affinity: vec![],
+            processors: None,
+            burst,
+            io: 0,
+            pending: VecDeque::new(),
+            ready_since: 0,
+            history: vec![],
+        }
+    }
+
+    pub fn with_arrival(mut self, arrival: u32) -> Self {
+        self.arrival = arrival;
+        self.ready_since = arrival;
+        self
+    }
+
+    /// follow the process' last cpu burst with an i/o wait of `io`, then another cpu burst.
+    pub fn with_io(mut self, io: u32, burst: u32) -> Self {
+        self.pending.push_back((io, burst));
+        self.io += io;
+        self.burst += burst;
+        self
+    }
+
+    /// the total cpu time requested by the process, regardless of how much has been served.
+    pub fn total_burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// the cpu and i/o times of a process that has not run yet, alternating and starting and
+    /// ending with cpu time, as a workload describes them.
+    pub fn bursts(&self) -> Vec<u32> {
+        let later: u32 = self.pending.iter().map(|(_, burst)| burst).sum();
+        let mut bursts = vec![self.burst - later];
+        for (io, burst) in &self.pending {
+            bursts.extend([*io, *burst]);
+        }
+        bursts
+    }
+
+    /// the total time the process spends waiting on i/o.
+    pub fn total_io(&self) -> u32 {
+        self.io
+    }
+
+    /// whether the process has no work left at all.
+    pub fn is_finished(&self) -> bool {
+        self.remaining_burst == 0 && self.pending.is_empty()
+    }
+
+    /// every burst the process has been given so far, in the order they were run.
+    pub fn history(&self) -> &[Burst] {
+        &self.history
+    }
+
+    /// wait time accumulates every time the process is dispatched
+    /// w += t - r
+    ///
+    /// where:
+    /// - r is the time the process last became ready
+    /// - t is the current time (i.e. time when the process switches to the running state)
+    fn dispatch(&mut self, time: u32) {
+        self.wait += time - self.ready_since;
+    }
+
+    pub fn run_burst(&mut self, time_at_start: u32, burst: u32, cpu: usize) {
+        self.running_time += burst;
+        self.remaining_burst -= burst;
+        self.ready_since = time_at_start + burst;
+        self.history.push(Burst {
+            start: time_at_start,
+            length: burst,
+            cpu,
+            blocked: false,
+        });
+    }
+
+    /// move on to the next cpu burst, returning how long the process must wait on i/o first.
+    fn block(&mut self) -> Option<u32> {
+        let (io, burst) = self.pending.pop_front()?;
+        if let Some(last) = self.history.last_mut() {
+            last.blocked = true;
+        }
+        self.remaining_burst = burst;
+        self.ready_since += io;
+        Some(io)
+    }
+}
+
+/// the index of a process within the simulation.
+pub type ProcessId = usize;
+
+/// a scheduling policy. the simulation engine owns the clock and the processes and asks the
+/// scheduler what to do whenever a process becomes ready or a cpu becomes free. with several
+/// cpus the scheduler keeps a single ready queue that every cpu takes work from.
+///
+/// a scheduler only needs to keep track of which processes are ready; every other detail is
+/// available from the `processes` slice each callback receives.
+pub trait Scheduler {
+    /// a process became ready to run, either for the first time or after waiting on i/o.
+    fn on_arrival(&mut self, id: ProcessId, processes: &[SimProcess]);
+
+    /// the running process used up its time quantum and is ready to run again.
+    fn on_quantum_expiry(&mut self, id: ProcessId, processes: &[SimProcess]) {
+        self.on_arrival(id, processes);
+    }
+
+    /// the running process left the cpu to wait on i/o. it is handed back through
+    /// `on_arrival` once the wait is over.
+    fn on_block(&mut self, _id: ProcessId, _processes: &[SimProcess]) {}
+
+    /// remove and return the ready process that should run next.
+    fn pick_next(&mut self, processes: &[SimProcess]) -> Option<ProcessId>;
+
+    /// the processes currently waiting to run, in the order the scheduler would serve them.
+    fn ready(&self) -> Vec<ProcessId>;
+
+    /// the longest a process may run before control returns to the scheduler. `None` lets
+    /// every burst run to completion.
+    fn quantum(&self) -> Option<u32> {
+        None
+    }
+
+    /// what the scheduler has learned about the processes, beyond the order of its ready queue,
+    /// for a snapshot to save. most schedulers keep nothing.
+    fn state(&self) -> Vec<u64> {
+        vec![]
+    }
+
+    /// take back what [`Scheduler::state`] saved, before the ready queue is handed over on
+    /// restore. a scheduler ignores state it does not recognise, such as another policy's.
+    fn restore_state(&mut self, _state: &[u64], _processes: &[SimProcess]) {}
+}
+
+/// where a process is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    /// the process has not arrived yet.
+    New,
+    Ready,
+    Running,
+    /// the process is waiting on i/o.
+    Blocked,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Action {
+    Arrival(ProcessId),
+    /// the slice running on a cpu is over.
+    SliceEnd(usize),
+    Wakeup(ProcessId),
+}
+
+/// an action due at a point in simulated time. the sequence number keeps actions that are due
+/// at the same time in the order they were scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Pending {
+    time: u32,
+    sequence: u64,
+    action: Action,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slice {
+    id: ProcessId,
+    start: u32,
+    length: u32,
+}
+
+/// a discrete-event simulation of one or more cpus driven by a pluggable scheduler.
+///
+/// the simulation can be run to completion with `run`, or advanced one scheduling event at a
+/// time with `step` and inspected in between.
+pub struct Simulation {
+    clock: u32,
+    processes: Vec<SimProcess>,
+    states: Vec<ProcessState>,
+    scheduler: Box<dyn Scheduler>,
+    pending: BinaryHeap<Reverse<Pending>>,
+    sequence: u64,
+    /// the slice each cpu is running, indexed by cpu.
+    running: Vec<Option<Slice>>,
+    /// the process each cpu ran last, indexed by cpu.
+    last: Vec<Option<ProcessId>>,
+    /// how long a cpu takes to switch from one process to a different one.
+    overhead: u32,
+    /// whether every cpu is idle and the idle event has been reported.
+    idle: bool,
+    finished: Vec<ProcessId>,
+}
+
+impl Simulation {
+    pub fn new(processes: Vec<SimProcess>, scheduler: Box<dyn Scheduler>) -> Self {
+        let mut simulation = Self {
+            clock: 0,
+            states: vec![ProcessState::New; processes.len()],
+            processes,
+            scheduler,
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            running: vec![None],
+            last: vec![None],
+            overhead: 0,
+            idle: false,
+            finished: vec![],
         };
+        for id in 0..simulation.processes.len() {
+            let arrival = simulation.processes[id].arrival;
+            simulation.schedule(arrival, Action::Arrival(id));
+        }
+        simulation
+    }
+
+    /// run the workload on `cpus` processors instead of one. must be called before the first
+    /// step.
+    pub fn with_cpus(mut self, cpus: usize) -> Self {
+        self.running = vec![None; cpus.max(1)];
+        self.last = vec![None; cpus.max(1)];
+        self
+    }
 
-        let priority = match components.next() {
-            Some(str) => str.parse::<u8>()?,
-            _ => {
-                return Err(ProgramError::InvalidProcessSpecification(String::from(
-                    value,
-                )))
+    /// charge `overhead` units of time whenever a cpu switches to a different process than the
+    /// one it ran last. the process starts running once the switch is over.
+    pub fn with_overhead(mut self, overhead: u32) -> Self {
+        self.overhead = overhead;
+        self
+    }
+
+    fn schedule(&mut self, time: u32, action: Action) {
+        self.pending.push(Reverse(Pending {
+            time,
+            sequence: self.sequence,
+            action,
+        }));
+        self.sequence += 1;
+    }
+
+    /// advance the clock to the next point in time where something happens, handle everything
+    /// due then and return what happened. `None` means the simulation is over.
+    pub fn step(&mut self) -> Option<Vec<Event>> {
+        let Reverse(first) = self.pending.pop()?;
+        self.clock = first.time;
+        let mut log = vec![];
+        self.handle(first.action, &mut log);
+        while let Some(Reverse(next)) = self.pending.peek() {
+            if next.time != self.clock {
+                break;
             }
-        };
+            let Reverse(next) = self.pending.pop().unwrap();
+            self.handle(next.action, &mut log);
+        }
+        self.dispatch(&mut log);
+        Some(log)
+    }
 
-        let burst = match components.next() {
-            Some(str) => str.parse::<u32>()?,
-            _ => {
-                return Err(ProgramError::InvalidProcessSpecification(String::from(
-                    value,
-                )))
+    fn handle(&mut self, action: Action, log: &mut Vec<Event>) {
+        match action {
+            Action::Arrival(id) => {
+                log.push(Event::new(
+                    self.clock,
+                    EventKind::Arrival,
+                    &self.processes[id].name,
+                ));
+                self.states[id] = ProcessState::Ready;
+                self.scheduler.on_arrival(id, &self.processes);
             }
-        };
-        let order = match components.next() {
-            Some(str) => match str.parse::<u8>()? {
-                0 => OrderKind::Burst,
-                1 => OrderKind::Priority,
-                _ => {
-                    return Err(ProgramError::InvalidProcessSpecification(String::from(
-                        value,
-                    )))
+            Action::Wakeup(id) => {
+                self.states[id] = ProcessState::Ready;
+                self.scheduler.on_arrival(id, &self.processes);
+            }
+            Action::SliceEnd(cpu) => {
+                let slice = match self.running[cpu].take() {
+                    Some(slice) => slice,
+                    None => return,
+                };
+                let process = &mut self.processes[slice.id];
+                process.run_burst(slice.start, slice.length, cpu);
+                if process.remaining_burst > 0 {
+                    log.push(Event::new(self.clock, EventKind::Preempt, &process.name));
+                    self.states[slice.id] = ProcessState::Ready;
+                    self.scheduler.on_quantum_expiry(slice.id, &self.processes);
+                } else if let Some(io) = process.block() {
+                    log.push(Event::new(self.clock, EventKind::Block, &process.name));
+                    self.states[slice.id] = ProcessState::Blocked;
+                    self.schedule(self.clock + io, Action::Wakeup(slice.id));
+                    self.scheduler.on_block(slice.id, &self.processes);
+                } else {
+                    log.push(Event::new(self.clock, EventKind::Complete, &process.name));
+                    self.states[slice.id] = ProcessState::Finished;
+                    self.finished.push(slice.id);
                 }
-            },
-            _ => {
-                return Err(ProgramError::InvalidProcessSpecification(String::from(
-                    value,
-                )))
             }
-        };
+        }
+    }
 
-        Ok(SimProcess::new(name, priority, burst, order))
+    /// hand every free cpu, lowest numbered first, the next ready process.
+    fn dispatch(&mut self, log: &mut Vec<Event>) {
+        for cpu in 0..self.running.len() {
+            if self.running[cpu].is_some() {
+                continue;
+            }
+            let id = match self.scheduler.pick_next(&self.processes) {
+                Some(id) => id,
+                None => break,
+            };
+            let start = match self.last[cpu] {
+                Some(last) if last != id => self.clock + self.overhead,
+                _ => self.clock,
+            };
+            let process = &mut self.processes[id];
+            process.dispatch(start);
+            let length = match self.scheduler.quantum() {
+                Some(quantum) => quantum.max(1).min(process.remaining_burst),
+                None => process.remaining_burst,
+            };
+            log.push(Event::new(self.clock, EventKind::Dispatch, &process.name));
+            self.states[id] = ProcessState::Running;
+            self.running[cpu] = Some(Slice { id, start, length });
+            self.last[cpu] = Some(id);
+            self.idle = false;
+            self.schedule(start + length, Action::SliceEnd(cpu));
+        }
+        if self.running.iter().all(Option::is_none) && !self.idle && !self.pending.is_empty() {
+            self.idle = true;
+            log.push(Event::idle(self.clock));
+        }
     }
-}
 
-impl PartialOrd for SimProcess {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.order {
-            OrderKind::Burst => self.remaining_burst.partial_cmp(&other.remaining_burst),
-            OrderKind::Priority => self.priority.partial_cmp(&other.priority),
+    /// run the simulation to completion, reporting everything that happens to `events`.
+    pub fn run(&mut self, events: &mut dyn EventSink) {
+        while let Some(log) = self.step() {
+            log.into_iter().for_each(|event| events.record(event));
         }
     }
-}
 
-impl PartialEq for SimProcess {
-    fn eq(&self, other: &Self) -> bool {
-        match self.order {
-            OrderKind::Burst => self.remaining_burst == other.remaining_burst,
-            OrderKind::Priority => self.priority == other.priority,
+    /// process every event due at or before `time`, leaving later events pending.
+    pub fn run_until(&mut self, time: u32, events: &mut dyn EventSink) {
+        while self.next_time().is_some_and(|next| next <= time) {
+            if let Some(log) = self.step() {
+                log.into_iter().for_each(|event| events.record(event));
+            }
         }
     }
-}
 
-impl SimProcess {
-    fn new(name: String, priority: u8, burst: u32, order: OrderKind) -> Self {
-        Self {
-            name,
-            priority,
-            remaining_burst: burst,
-            wait: 0,
-            running_time: 0,
-            order,
+    /// the current simulated time.
+    pub fn clock(&self) -> u32 {
+        self.clock
+    }
+
+    /// the time the next call to `step` will advance the clock to, if anything is left to do.
+    pub fn next_time(&self) -> Option<u32> {
+        self.pending.peek().map(|Reverse(next)| next.time)
+    }
+
+    /// move the arrival of a process that has not arrived yet to `time`, which may not be in
+    /// the past. this lets a what-if experiment branch from a snapshot with a different
+    /// arrival. returns whether the arrival was moved.
+    pub fn set_arrival(&mut self, id: ProcessId, time: u32) -> bool {
+        if self.states.get(id) != Some(&ProcessState::New) || time < self.clock {
+            return false;
         }
+        self.pending
+            .retain(|Reverse(pending)| pending.action != Action::Arrival(id));
+        self.processes[id].arrival = time;
+        self.processes[id].ready_since = time;
+        self.schedule(time, Action::Arrival(id));
+        true
     }
 
-    /// wait time is measured before a process is run, not afterward
-    /// w(r, t) = t - r
-    ///
-    /// where:
-    /// - r is the total previous runtime
-    /// - t is the current time (i.e. time when the process switches to the running state)
-    pub fn run_burst(&mut self, time_at_start: u32, burst: u32) {
-        let wait_time = time_at_start - self.running_time;
-        self.wait = wait_time;
-        self.running_time += burst;
-        self.remaining_burst -= burst;
-        println!(
-            "Time: {:06} | Burst Complete for {}",
-            time_at_start + burst,
-            &self
-        );
+    /// whether every process has finished. a finished simulation does not advance any further.
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// every process in the simulation, indexed by `ProcessId`. the counters of the running
+    /// process are brought up to date when its slice of cpu time ends.
+    pub fn processes(&self) -> &[SimProcess] {
+        &self.processes
+    }
+
+    pub fn state(&self, id: ProcessId) -> ProcessState {
+        self.states[id]
+    }
+
+    /// the number of cpus being simulated.
+    pub fn cpus(&self) -> usize {
+        self.running.len()
+    }
+
+    /// the processes on a cpu, in cpu order.
+    pub fn running(&self) -> Vec<ProcessId> {
+        self.running
+            .iter()
+            .flatten()
+            .map(|slice| slice.id)
+            .collect()
+    }
+
+    /// the process on a given cpu, if any.
+    pub fn running_on(&self, cpu: usize) -> Option<ProcessId> {
+        self.running[cpu].map(|slice| slice.id)
+    }
+
+    /// when the process on a given cpu was last given it.
+    pub fn running_since(&self, cpu: usize) -> Option<u32> {
+        self.running[cpu].map(|slice| slice.start)
+    }
+
+    /// the processes waiting to run, in the order the scheduler would serve them.
+    pub fn ready_queue(&self) -> Vec<ProcessId> {
+        self.scheduler.ready()
+    }
+
+    /// the processes in the order they finished, followed by any that never did.
+    pub fn into_processes(self) -> Vec<SimProcess> {
+        let mut slots: Vec<Option<SimProcess>> = self.processes.into_iter().map(Some).collect();
+        let mut ordered: Vec<SimProcess> = self
+            .finished
+            .iter()
+            .filter_map(|id| slots[*id].take())
+            .collect();
+        ordered.extend(slots.into_iter().flatten());
+        ordered
     }
 }
 
+/// run a workload to completion on a single cpu under a scheduler, reporting what happens to
+/// `events`. the processes are returned in the order they finished.
+pub fn simulate(
+    processes: Vec<SimProcess>,
+    scheduler: Box<dyn Scheduler>,
+    events: &mut dyn EventSink,
+) -> Vec<SimProcess> {
+    let mut simulation = Simulation::new(processes, scheduler);
+    simulation.run(events);
+    simulation.into_processes()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn build_reference_process() -> SimProcess {
-        SimProcess::new(String::from("T1"), 5, 25, OrderKind::Burst)
+        SimProcess::new(String::from("T1"), 5, 25)
     }
 
     #[test]
     fn parse_valid_process_string() -> Result<()> {
-        let line = "T1,5,25,1".to_string();
-        let process = SimProcess::try_from(line);
+        let process = SimProcess::try_from("T1,5,25");
 
         assert_eq!(build_reference_process(), process?);
 
         Ok(())
     }
 
+    #[test]
+    fn parse_optional_arrival() -> Result<()> {
+        let process = SimProcess::try_from("T1, 5, 25, 12")?;
+        assert_eq!(process.arrival, 12);
+        assert_eq!(process.remaining_burst, 25);
+        assert!(SimProcess::try_from("T1, 5, 25, 12, 0").is_err());
+        Ok(())
+    }
+
     #[test]
     fn parse_error_for_invalid_process_string() {
-        let line = String::from("T1, 23, ");
-        assert!(SimProcess::try_from(line).is_err());
+        assert!(SimProcess::try_from("T1, 23, ").is_err());
+        assert!(SimProcess::try_from("T1, 5, abc").is_err());
+        assert!(SimProcess::try_from("T1, 5, 25, 8, 0").is_err());
+    }
+
+    /// serves ready processes strictly in the order they became ready.
+    struct Fifo(VecDeque<ProcessId>);
+
+    impl Scheduler for Fifo {
+        fn on_arrival(&mut self, id: ProcessId, _processes: &[SimProcess]) {
+            self.0.push_back(id);
+        }
+
+        fn pick_next(&mut self, _processes: &[SimProcess]) -> Option<ProcessId> {
+            self.0.pop_front()
+        }
+
+        fn ready(&self) -> Vec<ProcessId> {
+            self.0.iter().copied().collect()
+        }
+    }
+
+    fn kinds(events: &[Event]) -> Vec<(u32, EventKind)> {
+        events.iter().map(|e| (e.time, e.kind)).collect()
+    }
+
+    #[test]
+    fn simulate_idles_until_arrival() {
+        let processes = vec![build_reference_process().with_arrival(10)];
+        let mut sink = crate::events::MemorySink::default();
+        let finished = simulate(processes, Box::new(Fifo(VecDeque::new())), &mut sink);
+        assert_eq!(
+            kinds(&sink.events),
+            vec![
+                (10, EventKind::Arrival),
+                (10, EventKind::Dispatch),
+                (35, EventKind::Complete),
+            ]
+        );
+        assert_eq!(finished[0].wait, 0);
+    }
 
-        let line = String::from("T1, 5, abc");
-        assert!(SimProcess::try_from(line).is_err());
+    #[test]
+    fn simulate_blocks_on_io() {
+        let processes = vec![
+            build_reference_process().with_io(20, 5),
+            SimProcess::new(String::from("T2"), 1, 10).with_arrival(5),
+        ];
+        let mut sink = crate::events::MemorySink::default();
+        let finished = simulate(processes, Box::new(Fifo(VecDeque::new())), &mut sink);
+        assert_eq!(
+            kinds(&sink.events),
+            vec![
+                (0, EventKind::Arrival),
+                (0, EventKind::Dispatch),
+                (5, EventKind::Arrival),
+                (25, EventKind::Block),
+                (25, EventKind::Dispatch),
+                (35, EventKind::Complete),
+                (35, EventKind::Idle),
+                (45, EventKind::Dispatch),
+                (50, EventKind::Complete),
+            ]
+        );
+        assert_eq!(finished[0].name, "T2");
+        assert_eq!(finished[0].wait, 20);
+        assert_eq!(finished[1].wait, 0);
+        assert_eq!(finished[1].total_burst(), 30);
+        assert_eq!(finished[1].total_io(), 20);
+        assert!(finished.iter().all(SimProcess::is_finished));
+    }
+
+    #[test]
+    fn step_exposes_intermediate_state() {
+        let processes = vec![
+            build_reference_process().with_io(20, 5),
+            SimProcess::new(String::from("T2"), 1, 10).with_arrival(5),
+        ];
+        let mut simulation = Simulation::new(processes, Box::new(Fifo(VecDeque::new())));
+        assert_eq!(simulation.state(1), ProcessState::New);
+
+        let log = simulation.step().unwrap();
+        assert_eq!(
+            kinds(&log),
+            vec![(0, EventKind::Arrival), (0, EventKind::Dispatch)]
+        );
+        assert_eq!(simulation.running(), vec![0]);
+
+        simulation.step();
+        assert_eq!(simulation.clock(), 5);
+        assert_eq!(simulation.ready_queue(), vec![1]);
+        assert_eq!(simulation.state(1), ProcessState::Ready);
+
+        simulation.step();
+        assert_eq!(simulation.clock(), 25);
+        assert_eq!(simulation.state(0), ProcessState::Blocked);
+        assert_eq!(simulation.running_on(0), Some(1));
+        assert_eq!(simulation.processes()[0].remaining_burst, 5);
+        assert!(simulation.ready_queue().is_empty());
 
-        let line = String::from("T1, 5, 25, 8");
-        assert!(SimProcess::try_from(line).is_err());
+        while simulation.step().is_some() {}
+        assert!(simulation.is_finished());
+        assert_eq!(simulation.clock(), 50);
+        assert_eq!(simulation.state(0), ProcessState::Finished);
+        assert!(simulation.running().is_empty());
+    }
+
+    #[test]
+    fn cpus_share_one_ready_queue() {
+        let processes = vec![
+            build_reference_process(),
+            SimProcess::new(String::from("T2"), 1, 10),
+            SimProcess::new(String::from("T3"), 1, 10).with_arrival(5),
+        ];
+        let mut simulation =
+            Simulation::new(processes, Box::new(Fifo(VecDeque::new()))).with_cpus(2);
+        simulation.step();
+        assert_eq!(simulation.running(), vec![0, 1]);
+        simulation.step();
+        assert_eq!(simulation.ready_queue(), vec![2]);
+        simulation.step();
+        assert_eq!(simulation.clock(), 10);
+        assert_eq!(simulation.running_on(1), Some(2));
+        assert_eq!(simulation.running_since(1), Some(10));
+
+        simulation.run(&mut crate::events::NullSink);
+        assert_eq!(simulation.clock(), 25);
+        let finished = simulation.into_processes();
+        let cpus: Vec<(&str, usize)> = finished
+            .iter()
+            .map(|process| (process.name.as_str(), process.history()[0].cpu))
+            .collect();
+        assert_eq!(cpus, vec![("T2", 1), ("T3", 1), ("T1", 0)]);
+    }
+
+    #[test]
+    fn overhead_delays_switches_to_other_processes() {
+        let processes = vec![
+            build_reference_process(),
+            SimProcess::new(String::from("T2"), 1, 10),
+        ];
+        let scheduler = Box::new(crate::algo::RoundRobin::new(10));
+        let mut simulation = Simulation::new(processes, scheduler).with_overhead(2);
+        simulation.run(&mut crate::events::NullSink);
+        let finished = simulation.into_processes();
+        let bursts: Vec<(u32, u32)> = finished[1]
+            .history()
+            .iter()
+            .map(|burst| (burst.start, burst.length))
+            .collect();
+        assert_eq!(bursts, vec![(0, 10), (24, 10), (34, 5)]);
+        assert_eq!(finished[0].history()[0].start, 12);
+        assert_eq!(finished[0].wait, 12);
+    }
+
+    #[test]
+    fn set_arrival_only_moves_future_arrivals() {
+        let processes = vec![
+            build_reference_process(),
+            SimProcess::new(String::from("T2"), 1, 10).with_arrival(50),
+        ];
+        let mut simulation = Simulation::new(processes, Box::new(Fifo(VecDeque::new())));
+        simulation.step();
+        assert!(!simulation.set_arrival(0, 10));
+        assert!(simulation.set_arrival(1, 10));
+        simulation.step();
+        assert_eq!(simulation.clock(), 10);
+        assert_eq!(simulation.state(1), ProcessState::Ready);
+        let finished = simulation.into_processes();
+        assert_eq!(finished[1].arrival, 10);
     }
 
     #[test]
     fn valid_display() {
-        let reference_display_string = "Process: T1     | Priority: 000005 | Running Time: 000000 | Remaining Burst: 000025 | Wait Time: 000000 | Order: Burst";
+        let reference_display_string = "Process: T1     | Priority: 000005 | Running Time: 000000 | Remaining Burst: 000025 | Wait Time: 000000";
         assert_eq!(
             build_reference_process().to_string(),
             reference_display_string
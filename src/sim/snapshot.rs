@@ -0,0 +1,601 @@
+//! saving, restoring and rewinding simulation state.
+//!
+//! a snapshot captures everything the engine needs to carry on from a point in time: the clock,
+//! every pending event, the per-process counters and the order of the ready queue. the engine
+//! is fully deterministic, so there is no random state to capture. schedulers are not saved;
+//! instead a restored simulation hands the ready queue to a fresh scheduler in the order it was
+//! saved, which also makes it possible to continue a run under a different policy. a scheduler
+//! that remembers more than its ready queue, such as the virtual runtimes of weighted fair
+//! sharing, saves that as well through [`Scheduler::state`].
+
+use super::{
+    Action, Burst, Event, Pending, ProcessId, ProcessState, Scheduler, SimProcess, Simulation,
+    Slice,
+};
+use crate::{ProgramError, Result};
+use std::cmp::Reverse;
+use std::io::{self, BufRead, Write};
+
+/// identifies the snapshot format, and its version, on the first line of a snapshot file.
+const HEADER: &str = "scheduler-snapshot 6";
+
+/// the complete state of a simulation at one point in time.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    clock: u32,
+    processes: Vec<SimProcess>,
+    states: Vec<ProcessState>,
+    ready: Vec<ProcessId>,
+    pending: Vec<Pending>,
+    sequence: u64,
+    running: Vec<Option<Slice>>,
+    last: Vec<Option<ProcessId>>,
+    overhead: u32,
+    idle: bool,
+    finished: Vec<ProcessId>,
+    /// what the scheduler saved through [`Scheduler::state`].
+    scheduler: Vec<u64>,
+}
+
+impl Simulation {
+    pub fn snapshot(&self) -> Snapshot {
+        let mut pending: Vec<Pending> = self.pending.iter().map(|Reverse(p)| *p).collect();
+        pending.sort();
+        Snapshot {
+            clock: self.clock,
+            processes: self.processes.clone(),
+            states: self.states.clone(),
+            ready: self.scheduler.ready(),
+            pending,
+            sequence: self.sequence,
+            running: self.running.clone(),
+            last: self.last.clone(),
+            overhead: self.overhead,
+            idle: self.idle,
+            finished: self.finished.clone(),
+            scheduler: self.scheduler.state(),
+        }
+    }
+
+    /// rebuild a simulation from a snapshot, continuing under `scheduler`.
+    pub fn restore(snapshot: &Snapshot, mut scheduler: Box<dyn Scheduler>) -> Self {
+        scheduler.restore_state(&snapshot.scheduler, &snapshot.processes);
+        for id in &snapshot.ready {
+            scheduler.on_arrival(*id, &snapshot.processes);
+        }
+        Self {
+            clock: snapshot.clock,
+            processes: snapshot.processes.clone(),
+            states: snapshot.states.clone(),
+            scheduler,
+            pending: snapshot.pending.iter().map(|p| Reverse(*p)).collect(),
+            sequence: snapshot.sequence,
+            running: snapshot.running.clone(),
+            last: snapshot.last.clone(),
+            overhead: snapshot.overhead,
+            idle: snapshot.idle,
+            finished: snapshot.finished.clone(),
+        }
+    }
+}
+
+fn invalid(message: String) -> ProgramError {
+    ProgramError::InvalidSnapshot(message)
+}
+
+fn state_name(state: ProcessState) -> &'static str {
+    match state {
+        ProcessState::New => "new",
+        ProcessState::Ready => "ready",
+        ProcessState::Running => "running",
+        ProcessState::Blocked => "blocked",
+        ProcessState::Finished => "finished",
+    }
+}
+
+fn parse_state(value: &str) -> Result<ProcessState> {
+    match value {
+        "new" => Ok(ProcessState::New),
+        "ready" => Ok(ProcessState::Ready),
+        "running" => Ok(ProcessState::Running),
+        "blocked" => Ok(ProcessState::Blocked),
+        "finished" => Ok(ProcessState::Finished),
+        _ => Err(invalid(format!("unknown process state '{}'", value))),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: Option<&str>, field: &str) -> Result<T> {
+    value
+        .and_then(|value| value.parse::<T>().ok())
+        .ok_or_else(|| invalid(format!("missing or invalid {}", field)))
+}
+
+/// write a list of items joined by commas, or `-` when the list is empty.
+fn join<T>(items: impl Iterator<Item = T>, format: impl Fn(T) -> String) -> String {
+    let joined: Vec<String> = items.map(format).collect();
+    match joined.is_empty() {
+        true => String::from("-"),
+        false => joined.join(","),
+    }
+}
+
+/// parse a list written by `join`, splitting each item into two numbers around `separator`.
+fn split_pairs(value: &str, separator: char, field: &str) -> Result<Vec<(u32, u32)>> {
+    if value == "-" {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|item| {
+            let mut parts = item.split(separator);
+            Ok((
+                parse_number(parts.next(), field)?,
+                parse_number(parts.next(), field)?,
+            ))
+        })
+        .collect()
+}
+
+fn parse_ids(values: std::str::SplitWhitespace<'_>) -> Result<Vec<ProcessId>> {
+    values
+        .map(|value| parse_number(Some(value), "process id"))
+        .collect()
+}
+
+impl Snapshot {
+    /// the simulated time the snapshot was taken at.
+    pub fn clock(&self) -> u32 {
+        self.clock
+    }
+
+    pub fn processes(&self) -> &[SimProcess] {
+        &self.processes
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "clock {}", self.clock)?;
+        writeln!(writer, "sequence {}", self.sequence)?;
+        writeln!(writer, "idle {}", self.idle)?;
+        writeln!(writer, "cpus {}", self.running.len())?;
+        writeln!(writer, "overhead {}", self.overhead)?;
+        for (cpu, last) in self.last.iter().enumerate() {
+            if let Some(id) = last {
+                writeln!(writer, "last {} {}", cpu, id)?;
+            }
+        }
+        for (cpu, slice) in self.running.iter().enumerate() {
+            if let Some(slice) = slice {
+                writeln!(
+                    writer,
+                    "running {} {} {} {}",
+                    cpu, slice.id, slice.start, slice.length
+                )?;
+            }
+        }
+        let ids =
+            |ids: &[ProcessId]| -> String { ids.iter().map(|id| format!(" {}", id)).collect() };
+        writeln!(writer, "finished{}", ids(&self.finished))?;
+        writeln!(writer, "ready{}", ids(&self.ready))?;
+        if !self.scheduler.is_empty() {
+            let state: String = self.scheduler.iter().map(|n| format!(" {}", n)).collect();
+            writeln!(writer, "scheduler{}", state)?;
+        }
+        for pending in &self.pending {
+            let action = match pending.action {
+                Action::Arrival(id) => format!("arrival {}", id),
+                Action::SliceEnd(cpu) => format!("slice-end {}", cpu),
+                Action::Wakeup(id) => format!("wakeup {}", id),
+            };
+            writeln!(
+                writer,
+                "pending {} {} {}",
+                pending.time, pending.sequence, action
+            )?;
+        }
+        for (process, state) in self.processes.iter().zip(&self.states) {
+            writeln!(
+                writer,
+                "process {} {} {} {} {} {} {} {} {} {} {} {}",
+                state_name(*state),
+                process.priority,
+                process.arrival,
+                process.remaining_burst,
+                process.running_time,
+                process.wait,
+                process.burst,
+                process.io,
+                process.ready_since,
+                join(process.pending.iter(), |(io, burst)| format!(
+                    "{}:{}",
+                    io, burst
+                )),
+                join(process.history.iter(), |burst| format!(
+                    "{}+{}@{}{}",
+                    burst.start,
+                    burst.length,
+                    burst.cpu,
+                    if burst.blocked { "!" } else { "" }
+                )),
+                process.name
+            )?;
+            // attributes the engine does not use follow their process, and only when set.
+            if let Some(deadline) = process.deadline {
+                writeln!(writer, "deadline {}", deadline)?;
+            }
+            if let Some(tickets) = process.tickets {
+                writeln!(writer, "tickets {}", tickets)?;
+            }
+            if let Some(processors) = process.processors {
+                writeln!(writer, "processors {}", processors)?;
+            }
+            if !process.affinity.is_empty() {
+                writeln!(
+                    writer,
+                    "affinity {}",
+                    join(process.affinity.iter(), |cpu| cpu.to_string())
+                )?;
+            }
+            if let Some(group) = &process.group {
+                writeln!(writer, "group {}", group)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line == HEADER => (),
+            _ => return Err(invalid(String::from("missing snapshot header"))),
+        }
+
+        let mut snapshot = Snapshot {
+            clock: 0,
+            processes: vec![],
+            states: vec![],
+            ready: vec![],
+            pending: vec![],
+            sequence: 0,
+            running: vec![None],
+            last: vec![None],
+            overhead: 0,
+            idle: false,
+            finished: vec![],
+            scheduler: vec![],
+        };
+        for line in lines {
+            let line = line?;
+            let (key, rest) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+            let mut values = rest.split_whitespace();
+            match key {
+                "clock" => snapshot.clock = parse_number(values.next(), "clock")?,
+                "sequence" => snapshot.sequence = parse_number(values.next(), "sequence")?,
+                "idle" => snapshot.idle = parse_number(values.next(), "idle")?,
+                "cpus" => match parse_number(values.next(), "cpus")? {
+                    0 => return Err(invalid(String::from("a snapshot needs at least one cpu"))),
+                    cpus => {
+                        snapshot.running = vec![None; cpus];
+                        snapshot.last = vec![None; cpus];
+                    }
+                },
+                "overhead" => snapshot.overhead = parse_number(values.next(), "overhead")?,
+                "last" => {
+                    let cpu: usize = parse_number(values.next(), "last cpu")?;
+                    let id = parse_number(values.next(), "last id")?;
+                    match snapshot.last.get_mut(cpu) {
+                        Some(last) => *last = Some(id),
+                        None => return Err(invalid(format!("cpu {} out of range", cpu))),
+                    }
+                }
+                "running" => {
+                    let cpu: usize = parse_number(values.next(), "running cpu")?;
+                    let slice = Slice {
+                        id: parse_number(values.next(), "running id")?,
+                        start: parse_number(values.next(), "running start")?,
+                        length: parse_number(values.next(), "running length")?,
+                    };
+                    match snapshot.running.get_mut(cpu) {
+                        Some(running) => *running = Some(slice),
+                        None => return Err(invalid(format!("cpu {} out of range", cpu))),
+                    }
+                }
+                "finished" => snapshot.finished = parse_ids(values)?,
+                "ready" => snapshot.ready = parse_ids(values)?,
+                "scheduler" => {
+                    snapshot.scheduler = values
+                        .map(|value| parse_number(Some(value), "scheduler state"))
+                        .collect::<Result<Vec<u64>>>()?
+                }
+                "pending" => {
+                    let time = parse_number(values.next(), "pending time")?;
+                    let sequence = parse_number(values.next(), "pending sequence")?;
+                    let action = match values.next() {
+                        Some("arrival") => Action::Arrival(parse_number(values.next(), "id")?),
+                        Some("wakeup") => Action::Wakeup(parse_number(values.next(), "id")?),
+                        Some("slice-end") => Action::SliceEnd(parse_number(values.next(), "cpu")?),
+                        _ => return Err(invalid(format!("unknown pending action: {}", line))),
+                    };
+                    snapshot.pending.push(Pending {
+                        time,
+                        sequence,
+                        action,
+                    });
+                }
+                "process" => {
+                    let (state, process) = read_process(rest)?;
+                    snapshot.states.push(state);
+                    snapshot.processes.push(process);
+                }
+                "deadline" | "tickets" | "processors" | "affinity" | "group" => {
+                    let process = match snapshot.processes.last_mut() {
+                        Some(process) => process,
+                        None => return Err(invalid(format!("{} before any process", key))),
+                    };
+                    match key {
+                        "deadline" => process.deadline = Some(parse_number(values.next(), key)?),
+                        "tickets" => process.tickets = Some(parse_number(values.next(), key)?),
+                        "processors" => {
+                            process.processors = Some(parse_number(values.next(), key)?)
+                        }
+                        "affinity" => {
+                            process.affinity = rest
+                                .split(',')
+                                .map(|cpu| parse_number(Some(cpu), key))
+                                .collect::<Result<Vec<usize>>>()?
+                        }
+                        _ => process.group = Some(String::from(rest)),
+                    }
+                }
+                "" => (),
+                _ => return Err(invalid(format!("unknown snapshot line: {}", line))),
+            }
+        }
+
+        let count = snapshot.processes.len();
+        let ids = snapshot
+            .ready
+            .iter()
+            .chain(&snapshot.finished)
+            .chain(snapshot.running.iter().flatten().map(|slice| &slice.id))
+            .chain(snapshot.last.iter().flatten());
+        if ids.copied().any(|id| id >= count) {
+            return Err(invalid(String::from("process id out of range")));
+        }
+        Ok(snapshot)
+    }
+}
+
+/// parse the bursts a process has run, written as `start+length@cpu`, followed by `!` when the
+/// process blocked on i/o at the end of the burst.
+fn read_history(value: &str) -> Result<Vec<Burst>> {
+    if value == "-" {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|item| {
+            let (item, blocked) = match item.strip_suffix('!') {
+                Some(item) => (item, true),
+                None => (item, false),
+            };
+            let (span, cpu) = item.split_once('@').unwrap_or((item, ""));
+            let (start, length) = span.split_once('+').unwrap_or((span, ""));
+            Ok(Burst {
+                start: parse_number(Some(start), "history start")?,
+                length: parse_number(Some(length), "history length")?,
+                cpu: parse_number(Some(cpu), "history cpu")?,
+                blocked,
+            })
+        })
+        .collect()
+}
+
+fn read_process(line: &str) -> Result<(ProcessState, SimProcess)> {
+    let fields: Vec<&str> = line.splitn(12, ' ').collect();
+    if fields.len() != 12 {
+        return Err(invalid(format!("incomplete process: {}", line)));
+    }
+    let state = parse_state(fields[0])?;
+    let mut process = SimProcess::new(
+        String::from(fields[11]),
+        parse_number(Some(fields[1]), "priority")?,
+        0,
+    );
+    process.arrival = parse_number(Some(fields[2]), "arrival")?;
+    process.remaining_burst = parse_number(Some(fields[3]), "remaining burst")?;
+    process.running_time = parse_number(Some(fields[4]), "running time")?;
+    process.wait = parse_number(Some(fields[5]), "wait")?;
+    process.burst = parse_number(Some(fields[6]), "burst")?;
+    process.io = parse_number(Some(fields[7]), "io")?;
+    process.ready_since = parse_number(Some(fields[8]), "ready since")?;
+    process.pending = split_pairs(fields[9], ':', "pending burst")?
+        .into_iter()
+        .collect();
+    process.history = read_history(fields[10])?;
+    Ok((state, process))
+}
+
+/// a simulation that keeps periodic checkpoints so it can be rewound to an earlier time.
+pub struct Timeline<F: Fn() -> Box<dyn Scheduler>> {
+    simulation: Simulation,
+    checkpoints: Vec<Snapshot>,
+    interval: u32,
+    scheduler: F,
+}
+
+impl<F: Fn() -> Box<dyn Scheduler>> Timeline<F> {
+    /// wrap a simulation, checkpointing it at least every `interval` units of simulated time.
+    /// `scheduler` builds the fresh policy a checkpoint is restored under.
+    pub fn new(simulation: Simulation, interval: u32, scheduler: F) -> Self {
+        Self {
+            checkpoints: vec![simulation.snapshot()],
+            simulation,
+            interval: interval.max(1),
+            scheduler,
+        }
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub fn step(&mut self) -> Option<Vec<Event>> {
+        let log = self.simulation.step()?;
+        let last = self.checkpoints.last().map(Snapshot::clock).unwrap_or(0);
+        if self.simulation.clock() >= last + self.interval {
+            self.checkpoints.push(self.simulation.snapshot());
+        }
+        Some(log)
+    }
+
+    /// return the simulation to the state it was in once everything due at or before `time`
+    /// had happened. checkpoints taken after the one restored are discarded.
+    pub fn rewind(&mut self, time: u32) {
+        while self.checkpoints.len() > 1 && self.checkpoints.last().unwrap().clock() > time {
+            self.checkpoints.pop();
+        }
+        let checkpoint = self.checkpoints.last().unwrap();
+        self.simulation = Simulation::restore(checkpoint, (self.scheduler)());
+        while self.simulation.next_time().is_some_and(|next| next <= time) {
+            self.simulation.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo::{RoundRobin, WeightedFair};
+    use crate::events::MemorySink;
+
+    fn build_simulation() -> Simulation {
+        let mut grouped = SimProcess::new(String::from("T3"), 3, 30);
+        grouped.deadline = Some(90);
+        grouped.group = Some(String::from("ops team"));
+        grouped.tickets = Some(20);
+        grouped.affinity = vec![0, 1];
+        grouped.processors = Some(4);
+        let processes = vec![
+            SimProcess::new(String::from("T1"), 1, 25).with_io(20, 5),
+            SimProcess::new(String::from("T 2"), 2, 10).with_arrival(5),
+            grouped,
+        ];
+        Simulation::new(processes, Box::new(RoundRobin::new(10)))
+            .with_cpus(2)
+            .with_overhead(1)
+    }
+
+    fn finish(mut simulation: Simulation) -> Vec<Event> {
+        let mut sink = MemorySink::default();
+        simulation.run(&mut sink);
+        sink.events
+    }
+
+    #[test]
+    fn restored_simulation_continues_identically() {
+        let mut simulation = build_simulation();
+        for _ in 0..4 {
+            simulation.step();
+        }
+        let snapshot = simulation.snapshot();
+        let restored = Simulation::restore(&snapshot, Box::new(RoundRobin::new(10)));
+        assert_eq!(finish(restored), finish(simulation));
+    }
+
+    #[test]
+    fn restored_fair_run_continues_identically() {
+        let fair = || Box::new(WeightedFair::new(2)) as Box<dyn Scheduler>;
+        // C arrives late and A comes back from i/o, so both are moved up to the virtual runtime
+        // of the others, which only the saved state remembers.
+        let processes = vec![
+            SimProcess::new(String::from("A"), 5, 8).with_io(6, 10),
+            SimProcess::new(String::from("B"), 5, 20),
+            SimProcess::new(String::from("C"), 3, 10).with_arrival(12),
+        ];
+        let uninterrupted = finish(Simulation::new(processes.clone(), fair()));
+
+        // restore from a snapshot taken after every step, through its text form.
+        let mut simulation = Simulation::new(processes, fair());
+        let mut so_far = vec![];
+        loop {
+            let mut buffer: Vec<u8> = vec![];
+            simulation.snapshot().write(&mut buffer).unwrap();
+            let read = Snapshot::read(&buffer[..]).unwrap();
+            let mut events = so_far.clone();
+            events.extend(finish(Simulation::restore(&read, fair())));
+            assert_eq!(events, uninterrupted, "restored at {}", read.clock());
+            match simulation.step() {
+                Some(log) => so_far.extend(log),
+                None => break,
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_text() {
+        let mut simulation = build_simulation();
+        for _ in 0..5 {
+            simulation.step();
+        }
+        let snapshot = simulation.snapshot();
+        let mut buffer: Vec<u8> = vec![];
+        snapshot.write(&mut buffer).unwrap();
+        let read = Snapshot::read(&buffer[..]).unwrap();
+
+        let mut rewritten: Vec<u8> = vec![];
+        read.write(&mut rewritten).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            String::from_utf8(rewritten).unwrap()
+        );
+        assert_eq!(read.processes()[1].name, "T 2");
+        assert_eq!(read.processes()[2].group.as_deref(), Some("ops team"));
+        assert_eq!(read.processes()[2].affinity, vec![0, 1]);
+
+        let restored = Simulation::restore(&read, Box::new(RoundRobin::new(10)));
+        assert_eq!(finish(restored), finish(simulation));
+    }
+
+    #[test]
+    fn history_keeps_blocks() {
+        let history = read_history("0+10@0,12+5@1!").unwrap();
+        let blocked: Vec<bool> = history.iter().map(|burst| burst.blocked).collect();
+        assert_eq!(blocked, vec![false, true]);
+        assert_eq!((history[1].start, history[1].cpu), (12, 1));
+    }
+
+    #[test]
+    fn read_rejects_malformed_snapshots() {
+        assert!(Snapshot::read("clock 5\n".as_bytes()).is_err());
+        let text = format!("{}\nclock five\n", HEADER);
+        assert!(Snapshot::read(text.as_bytes()).is_err());
+        let text = format!("{}\nready 3\n", HEADER);
+        assert!(Snapshot::read(text.as_bytes()).is_err());
+        let text = format!("{}\ncpus 1\nrunning 1 0 0 10\n", HEADER);
+        assert!(Snapshot::read(text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rewind_returns_to_earlier_state() {
+        let mut timeline = Timeline::new(build_simulation(), 15, || {
+            Box::new(RoundRobin::new(10)) as Box<dyn Scheduler>
+        });
+        let mut states = vec![];
+        while timeline.step().is_some() {
+            let simulation = timeline.simulation();
+            states.push((
+                simulation.clock(),
+                simulation.running(),
+                simulation.ready_queue(),
+            ));
+        }
+        for (clock, running, ready) in states.into_iter().rev() {
+            timeline.rewind(clock);
+            let simulation = timeline.simulation();
+            assert_eq!(simulation.clock(), clock);
+            assert_eq!(simulation.running(), running);
+            assert_eq!(simulation.ready_queue(), ready);
+        }
+    }
+}
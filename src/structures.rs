@@ -42,6 +42,12 @@ pub struct DLL<T> {
     _marker: marker::PhantomData<T>,
 }
 
+impl<T> Default for DLL<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> DLL<T> {
     pub fn new() -> Self {
         Self {
@@ -78,10 +84,10 @@ impl<T> DLL<T> {
                 self.head = Some(new_node);
                 self.tail = Some(new_node);
             } else {
-                self.head.map(|head| {
+                if let Some(head) = self.head {
                     DLLNode::enchain(new_node, head);
                     self.head = Some(new_node);
-                });
+                }
             }
         }
         self.length += 1;
@@ -153,11 +159,7 @@ impl<T> DLL<T> {
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter { list: self }
-    }
-
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             front: self.head,
             back: self.tail,
@@ -166,7 +168,7 @@ impl<T> DLL<T> {
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             front: self.head,
             back: self.tail,
@@ -188,6 +190,15 @@ impl<T> From<Vec<T>> for DLL<T> {
     }
 }
 
+impl<T> IntoIterator for DLL<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
@@ -279,13 +290,13 @@ impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
 
 impl<T> Drop for DLL<T> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop_back() {}
+        while self.pop_back().is_some() {}
     }
 }
 
-impl<T> Into<Vec<T>> for DLL<T> {
-    fn into(self) -> Vec<T> {
-        Vec::from_iter(self.into_iter())
+impl<T> From<DLL<T>> for Vec<T> {
+    fn from(list: DLL<T>) -> Self {
+        Vec::from_iter(list)
     }
 }
 
@@ -295,9 +306,7 @@ impl<T: PartialEq> PartialEq for DLL<T> {
             return false;
         }
         let mut flag = true;
-        let mut zipped = self.iter().zip(other.iter());
-
-        while let Some((x, y)) = zipped.next() {
+        for (x, y) in self.iter().zip(other.iter()) {
             if x != y {
                 flag = false;
             }
@@ -382,7 +391,7 @@ mod tests {
         fn obtain_list() -> DLL<u8> {
             let mut list = DLL::new();
             obtain_range().for_each(|x| list.append(x));
-            return list;
+            list
         }
 
         #[test]
@@ -0,0 +1,311 @@
+use crate::events::NullSink;
+use crate::metrics::{self, Metric, Summary};
+use crate::sim::SimProcess;
+use crate::{Parameters, ScheduleKind};
+use crate::{ProgramError, Result};
+use std::io::{self, Write};
+use std::result;
+
+/// a numeric scheduler parameter that can be swept over a range of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Parameter {
+    Quantum,
+    Cpus,
+    Overhead,
+}
+
+impl Parameter {
+    pub const ALL: [Parameter; 3] = [Parameter::Quantum, Parameter::Cpus, Parameter::Overhead];
+
+    /// the identifier used on the command line and in csv headers.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Parameter::Quantum => "quantum",
+            Parameter::Cpus => "cpus",
+            Parameter::Overhead => "overhead",
+        }
+    }
+
+    /// the smallest value the simulation can be run with.
+    pub fn minimum(&self) -> u32 {
+        match self {
+            Parameter::Quantum | Parameter::Cpus => 1,
+            Parameter::Overhead => 0,
+        }
+    }
+
+    pub fn apply(&self, parameters: &mut Parameters, value: u32) {
+        match self {
+            Parameter::Quantum => parameters.quantum = value,
+            Parameter::Cpus => parameters.cpus = value as usize,
+            Parameter::Overhead => parameters.overhead = value,
+        }
+    }
+}
+
+impl TryFrom<&str> for Parameter {
+    type Error = ProgramError;
+    fn try_from(value: &str) -> Result<Self> {
+        Parameter::ALL
+            .into_iter()
+            .find(|parameter| parameter.key() == value)
+            .ok_or(ProgramError::InvalidCommandInput)
+    }
+}
+
+/// the settings describing a parameter sweep.
+#[derive(Debug, PartialEq)]
+pub struct Sweep {
+    pub parameter: Parameter,
+    pub start: u32,
+    pub end: u32,
+    pub step: u32,
+    /// the metric the best parameter value is chosen by.
+    pub objective: Metric,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            parameter: Parameter::Quantum,
+            start: 1,
+            end: 50,
+            step: 1,
+            objective: Metric::Wait,
+        }
+    }
+}
+
+impl Sweep {
+    pub fn values(&self) -> impl Iterator<Item = u32> {
+        (self.start..=self.end).step_by(self.step as usize)
+    }
+}
+
+/// parse an inclusive range given as `start:end` or `start:end:step`.
+pub fn parse_range(value: &str) -> Result<(u32, u32, u32)> {
+    let bounds = value
+        .split(':')
+        .map(|bound| bound.trim().parse::<u32>())
+        .collect::<result::Result<Vec<u32>, _>>()?;
+    match bounds[..] {
+        [start, end] if start <= end => Ok((start, end, 1)),
+        [start, end, step] if start <= end && step > 0 => Ok((start, end, step)),
+        _ => Err(ProgramError::InvalidCommandInput),
+    }
+}
+
+/// the outcome of running the workload with one value of the swept parameter.
+#[derive(Debug)]
+pub struct SweepPoint {
+    pub value: u32,
+    pub summary: Summary,
+}
+
+/// run a scheduler over the workload once for every value in the sweep.
+pub fn run_sweep(
+    kind: ScheduleKind,
+    workload: &[SimProcess],
+    sweep: &Sweep,
+    base: &Parameters,
+) -> Vec<SweepPoint> {
+    sweep
+        .values()
+        .map(|value| {
+            let mut parameters = *base;
+            sweep.parameter.apply(&mut parameters, value);
+            let finished = crate::schedule(kind, workload.to_vec(), &parameters, &mut NullSink);
+            SweepPoint {
+                value,
+                summary: metrics::summarize(&finished),
+            }
+        })
+        .collect()
+}
+
+/// the point that performs best on the objective. ties go to the smallest parameter value.
+pub fn best_point(points: &[SweepPoint], objective: Metric) -> Option<&SweepPoint> {
+    points.iter().reduce(|best, point| {
+        match objective.is_better(
+            objective.value(&point.summary),
+            objective.value(&best.summary),
+        ) {
+            true => point,
+            false => best,
+        }
+    })
+}
+
+/// render the metrics of every point as a table, flagging the row that is best on the
+/// objective.
+pub fn sweep_table(points: &[SweepPoint], parameter: Parameter, objective: Metric) -> String {
+    let best = best_point(points, objective).map(|point| point.value);
+    let mut table = format!("{:>10}", parameter.key());
+    for metric in Metric::ALL {
+        table.push_str(&format!(" | {:>16}", metric.header()));
+    }
+    table.push('\n');
+    table.push_str(&"-".repeat(10 + Metric::ALL.len() * 19));
+    table.push('\n');
+
+    for point in points {
+        let marker = match Some(point.value) == best {
+            true => '*',
+            false => ' ',
+        };
+        table.push_str(&format!("{:>9}{}", point.value, marker));
+        for metric in Metric::ALL {
+            table.push_str(&format!(
+                " | {:>16.precision$}",
+                metric.value(&point.summary),
+                precision = metric.precision()
+            ));
+        }
+        table.push('\n');
+    }
+    if let Some(point) = best_point(points, objective) {
+        table.push_str(&format!(
+            "* best {} for {}: {} ({:.precision$})\n",
+            parameter.key(),
+            objective.key(),
+            point.value,
+            objective.value(&point.summary),
+            precision = objective.precision()
+        ));
+    }
+    table
+}
+
+/// write the metrics of every point as csv with a header row.
+pub fn write_csv<W: Write>(
+    writer: &mut W,
+    points: &[SweepPoint],
+    parameter: Parameter,
+) -> io::Result<()> {
+    let headers: Vec<&str> = Metric::ALL.iter().map(|metric| metric.key()).collect();
+    writeln!(writer, "{},{}", parameter.key(), headers.join(","))?;
+    for point in points {
+        let values: Vec<String> = Metric::ALL
+            .iter()
+            .map(|metric| metric.value(&point.summary).to_string())
+            .collect();
+        writeln!(writer, "{},{}", point.value, values.join(","))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_workload() -> Vec<SimProcess> {
+        crate::read_workload(crate::DEFAULT_PROCESS_FILENAME, None).unwrap()
+    }
+
+    fn build_sweep() -> Sweep {
+        Sweep {
+            start: 5,
+            end: 50,
+            step: 5,
+            ..Sweep::default()
+        }
+    }
+
+    #[test]
+    fn parse_valid_ranges() {
+        assert_eq!(parse_range("1:10").unwrap(), (1, 10, 1));
+        assert_eq!(parse_range("5:50:5").unwrap(), (5, 50, 5));
+    }
+
+    #[test]
+    fn parse_invalid_ranges() {
+        assert!(parse_range("10:1").is_err());
+        assert!(parse_range("1:10:0").is_err());
+        assert!(parse_range("1").is_err());
+        assert!(parse_range("a:b").is_err());
+    }
+
+    #[test]
+    fn one_point_per_value() {
+        let points = run_sweep(
+            ScheduleKind::RR,
+            &build_workload(),
+            &build_sweep(),
+            &Parameters::default(),
+        );
+        let values: Vec<u32> = points.iter().map(|point| point.value).collect();
+        assert_eq!(values, vec![5, 10, 15, 20, 25, 30, 35, 40, 45, 50]);
+    }
+
+    #[test]
+    fn large_quantum_degenerates_to_fcfs() {
+        let workload = build_workload();
+        let sweep = Sweep {
+            start: 100,
+            end: 100,
+            ..Sweep::default()
+        };
+        let points = run_sweep(ScheduleKind::RR, &workload, &sweep, &Parameters::default());
+        let fcfs = metrics::summarize(&crate::algo::fcfs(workload, &mut NullSink));
+        assert_eq!(points[0].summary, fcfs);
+    }
+
+    #[test]
+    fn more_cpus_never_take_longer() {
+        let sweep = Sweep {
+            parameter: Parameter::Cpus,
+            start: 1,
+            end: 4,
+            ..Sweep::default()
+        };
+        let points = run_sweep(
+            ScheduleKind::FCFS,
+            &build_workload(),
+            &sweep,
+            &Parameters::default(),
+        );
+        let makespans: Vec<u32> = points.iter().map(|point| point.summary.makespan).collect();
+        assert!(makespans.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(makespans[3] < makespans[0]);
+    }
+
+    #[test]
+    fn best_point_minimizes_objective() {
+        let points = run_sweep(
+            ScheduleKind::RR,
+            &build_workload(),
+            &build_sweep(),
+            &Parameters::default(),
+        );
+        let best = best_point(&points, Metric::ContextSwitches).unwrap();
+        let fewest = points
+            .iter()
+            .map(|point| point.summary.context_switches)
+            .min()
+            .unwrap();
+        assert_eq!(best.summary.context_switches, fewest);
+        assert!(points
+            .iter()
+            .filter(|point| point.value < best.value)
+            .all(|point| point.summary.context_switches > fewest));
+    }
+
+    #[test]
+    fn csv_has_header_and_row_per_point() {
+        let points = run_sweep(
+            ScheduleKind::RR,
+            &build_workload(),
+            &build_sweep(),
+            &Parameters::default(),
+        );
+        let mut buffer: Vec<u8> = vec![];
+        write_csv(&mut buffer, &points, Parameter::Quantum).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        let mut lines = document.lines();
+        assert_eq!(
+            lines.next(),
+            Some("quantum,wait,turnaround,response,switches,makespan,fairness")
+        );
+        assert_eq!(lines.count(), points.len());
+    }
+}
@@ -0,0 +1,606 @@
+//! a reader for the subset of toml used by scenario files.
+//!
+//! supported: comments, `key = value` pairs with bare or quoted keys, `[table]` and
+//! `[[array of tables]]` headers one level deep, basic and literal strings, integers, floats,
+//! booleans, arrays (which may span lines) and inline tables. dotted keys, multi-line strings and
+//! dates are not.
+
+use crate::{ProgramError, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    /// how the value is described in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a number",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+            Value::Table(_) => "a table",
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// the value as a float, converting integers.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => Some(*value),
+            Value::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+}
+
+/// keys and their values, in the order they were written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    entries: Vec<(String, Value)>,
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// add an entry, which the caller has checked is not a duplicate.
+    pub(crate) fn push(&mut self, key: String, value: Value) {
+        self.entries.push((key, value));
+    }
+}
+
+// reading values out of documents, naming the key and where it was found in any mistake.
+
+fn invalid<T>(message: String) -> Result<T> {
+    Err(ProgramError::InvalidDocument(message))
+}
+
+/// reject keys the document does not know, which are most likely typos.
+pub(crate) fn check_keys(table: &Table, known: &[&str], context: &str) -> Result<()> {
+    match table.iter().find(|(key, _)| !known.contains(key)) {
+        Some((key, _)) => invalid(format!(
+            "unknown key '{}' in {}, expected one of {}",
+            key,
+            context,
+            known.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn string<'a>(table: &'a Table, key: &str, context: &str) -> Result<Option<&'a str>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(value) => invalid(format!(
+            "'{}' in {} should be a string, not {}",
+            key,
+            context,
+            value.type_name()
+        )),
+    }
+}
+
+/// the value of `key` as an integer of type `T` that is at least `minimum`.
+pub(crate) fn integer<T>(value: &Value, key: &str, context: &str, minimum: T) -> Result<T>
+where
+    T: TryFrom<i64> + PartialOrd + std::fmt::Display,
+{
+    match value {
+        Value::Integer(value) => match T::try_from(*value) {
+            Ok(number) if number >= minimum => Ok(number),
+            Err(_) if *value > 0 => {
+                invalid(format!("'{}' in {} is too large: {}", key, context, value))
+            }
+            _ => invalid(format!(
+                "'{}' in {} should be at least {}, not {}",
+                key, context, minimum, value
+            )),
+        },
+        value => invalid(format!(
+            "'{}' in {} should be an integer, not {}",
+            key,
+            context,
+            value.type_name()
+        )),
+    }
+}
+
+pub(crate) fn number<T>(table: &Table, key: &str, context: &str, minimum: T) -> Result<Option<T>>
+where
+    T: TryFrom<i64> + PartialOrd + std::fmt::Display,
+{
+    table
+        .get(key)
+        .map(|value| integer(value, key, context, minimum))
+        .transpose()
+}
+
+/// the tables of a `[[name]]` array, which may be missing.
+pub(crate) fn tables<'a>(root: &'a Table, key: &str) -> Result<Vec<&'a Table>> {
+    match root.get(key) {
+        None => Ok(vec![]),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Value::Table(table) => Ok(table),
+                _ => invalid(format!("'{}' should be written as [[{}]] tables", key, key)),
+            })
+            .collect(),
+        Some(_) => invalid(format!("'{}' should be written as [[{}]] tables", key, key)),
+    }
+}
+
+/// which table `key = value` lines are currently added to.
+enum Section {
+    Root,
+    Table(String),
+    ArrayItem(String),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: String) -> Result<T> {
+        Err(ProgramError::InvalidDocument(format!(
+            "line {}: {}",
+            self.line, message
+        )))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => self.error(format!("expected '{}' but found '{}'", expected, c)),
+            None => self.error(format!("expected '{}' but the document ended", expected)),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.next();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.next();
+            }
+        }
+    }
+
+    /// skip whitespace, line breaks and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// finish a line, allowing only a trailing comment.
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.next();
+        }
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some(c) => self.error(format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        let key = match self.peek() {
+            Some('"') => self.basic_string()?,
+            Some('\'') => self.literal_string()?,
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self
+                    .peek()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+                {
+                    key.push(c);
+                    self.next();
+                }
+                if key.is_empty() {
+                    return match self.peek() {
+                        Some(c) => self.error(format!("expected a key but found '{}'", c)),
+                        None => self.error(String::from("expected a key")),
+                    };
+                }
+                key
+            }
+        };
+        self.skip_spaces();
+        if self.peek() == Some('.') {
+            return self.error(format!("dotted keys are not supported ('{}.')", key));
+        }
+        Ok(key)
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            if matches!(self.peek(), None | Some('\n')) {
+                return self.error(String::from("unterminated string"));
+            }
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('u') => {
+                            let digits: String = (0..4).filter_map(|_| self.next()).collect();
+                            match u32::from_str_radix(&digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                            {
+                                Some(c) => c,
+                                None => return self.error(format!("invalid escape \\u{}", digits)),
+                            }
+                        }
+                        Some(c) => return self.error(format!("invalid escape \\{}", c)),
+                        None => return self.error(String::from("unterminated string")),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) => value.push(c),
+                None => unreachable!("checked above"),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        self.expect('\'')?;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') => {
+                    self.next();
+                    return Ok(value);
+                }
+                Some('\n') | None => return self.error(String::from("unterminated string")),
+                Some(c) => {
+                    self.next();
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => self.error(String::from("expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut values = vec![];
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.next();
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_blank();
+            match self.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return self.error(String::from("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Table::default();
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_spaces();
+            let key = self.key()?;
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            self.insert(&mut table, key, value)?;
+            self.skip_spaces();
+            match self.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return self.error(String::from("expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    /// booleans and numbers, which run until the next delimiter.
+    fn scalar(&mut self) -> Result<Value> {
+        let mut word = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '}' | '#'))
+        {
+            word.push(c);
+            self.next();
+        }
+        match word.as_str() {
+            "true" => return Ok(Value::Boolean(true)),
+            "false" => return Ok(Value::Boolean(false)),
+            _ => (),
+        }
+        let digits = word.replace('_', "");
+        let parsed = match digits.contains(['.', 'e', 'E']) {
+            true => digits.parse::<f64>().ok().map(Value::Float),
+            false => digits.parse::<i64>().ok().map(Value::Integer),
+        };
+        match parsed {
+            Some(value) => Ok(value),
+            None => self.error(format!("invalid value '{}'", word)),
+        }
+    }
+
+    fn insert(&self, table: &mut Table, key: String, value: Value) -> Result<()> {
+        if table.get(&key).is_some() {
+            return self.error(format!("duplicate key '{}'", key));
+        }
+        table.entries.push((key, value));
+        Ok(())
+    }
+
+    /// parse a `[name]` or `[[name]]` header and make room for the section it opens.
+    fn header(&mut self, root: &mut Table) -> Result<Section> {
+        self.expect('[')?;
+        let array = self.peek() == Some('[');
+        if array {
+            self.next();
+        }
+        self.skip_spaces();
+        let name = self.key()?;
+        self.expect(']')?;
+        if array {
+            self.expect(']')?;
+        }
+        let section = match (array, root.get_mut(&name)) {
+            (false, None) => {
+                root.entries
+                    .push((name.clone(), Value::Table(Table::default())));
+                Ok(Section::Table(name))
+            }
+            (false, Some(_)) => self.error(format!("table '{}' is defined twice", name)),
+            (true, None) => {
+                root.entries.push((
+                    name.clone(),
+                    Value::Array(vec![Value::Table(Table::default())]),
+                ));
+                Ok(Section::ArrayItem(name))
+            }
+            (true, Some(Value::Array(items))) if items.iter().all(|i| i.as_table().is_some()) => {
+                items.push(Value::Table(Table::default()));
+                Ok(Section::ArrayItem(name))
+            }
+            (true, Some(_)) => self.error(format!("'{}' is not an array of tables", name)),
+        }?;
+        self.end_of_line()?;
+        Ok(section)
+    }
+}
+
+/// parse a whole document into its root table.
+pub fn parse(text: &str) -> Result<Table> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+    let mut root = Table::default();
+    let mut section = Section::Root;
+    loop {
+        parser.skip_blank();
+        match parser.peek() {
+            None => return Ok(root),
+            Some('[') => section = parser.header(&mut root)?,
+            Some(_) => {
+                let line = parser.line;
+                let key = parser.key()?;
+                parser.expect('=')?;
+                parser.skip_spaces();
+                let value = parser.value()?;
+                // report problems with the pair on the line it started on.
+                let end = std::mem::replace(&mut parser.line, line);
+                let table = match &section {
+                    Section::Root => &mut root,
+                    Section::Table(name) => match root.get_mut(name) {
+                        Some(Value::Table(table)) => table,
+                        _ => unreachable!("header() always creates the table"),
+                    },
+                    Section::ArrayItem(name) => match root.get_mut(name) {
+                        Some(Value::Array(items)) => match items.last_mut() {
+                            Some(Value::Table(table)) => table,
+                            _ => unreachable!("header() always appends a table"),
+                        },
+                        _ => unreachable!("header() always creates the array"),
+                    },
+                };
+                parser.insert(table, key, value)?;
+                parser.line = end;
+                parser.end_of_line()?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tables_arrays_and_values() {
+        let text = "\
+# an experiment
+name = \"convoy \\\"demo\\\"\" # trailing comment
+path = 'C:\\data'
+count = 1_000
+ratio = 0.5
+enabled = true
+sizes = [
+    1, 2,
+    3, # the last one
+]
+
+[limits]
+cpu = { cores = 4, name = \"x\" }
+
+[[run]]
+id = 1
+
+[[run]]
+id = 2
+";
+        let root = parse(text).unwrap();
+        assert_eq!(
+            root.get("name"),
+            Some(&Value::String(String::from("convoy \"demo\"")))
+        );
+        assert_eq!(root.get("path").and_then(Value::as_str), Some("C:\\data"));
+        assert_eq!(root.get("count").and_then(Value::as_integer), Some(1000));
+        assert_eq!(root.get("ratio").and_then(Value::as_float), Some(0.5));
+        assert_eq!(root.get("enabled").and_then(Value::as_bool), Some(true));
+        assert_eq!(
+            root.get("sizes").and_then(Value::as_array).map(|a| a.len()),
+            Some(3)
+        );
+        let cpu = root
+            .get("limits")
+            .and_then(Value::as_table)
+            .and_then(|limits| limits.get("cpu"))
+            .and_then(Value::as_table)
+            .unwrap();
+        assert_eq!(cpu.get("cores").and_then(Value::as_integer), Some(4));
+        let runs = root.get("run").and_then(Value::as_array).unwrap();
+        let ids: Vec<i64> = runs
+            .iter()
+            .filter_map(|run| run.as_table()?.get("id")?.as_integer())
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let message = |text: &str| match parse(text) {
+            Err(ProgramError::InvalidDocument(message)) => message,
+            other => panic!("received unexpected result: {:?}", other),
+        };
+        assert_eq!(message("a = 1\na = 2\n"), "line 2: duplicate key 'a'");
+        assert_eq!(message("\n\nb = \"open\n"), "line 3: unterminated string");
+        assert_eq!(message("c = 1 2\n"), "line 1: unexpected '2' after value");
+        assert_eq!(message("[t]\n[t]\n"), "line 2: table 't' is defined twice");
+        assert_eq!(message("d = tru\n"), "line 1: invalid value 'tru'");
+        assert!(parse("a.b = 1\n").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::sim::SimProcess;
+use std::io::{self, Write};
+
+/// simulated time is measured in milliseconds while the trace event format expects
+/// microseconds.
+const MICROS_PER_TICK: u64 = 1000;
+
+/// escape a string for inclusion between double quotes in a json document.
+pub(crate) fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn ts(time: u32) -> u64 {
+    time as u64 * MICROS_PER_TICK
+}
+
+/// build the individual trace events for a finished schedule.
+///
+/// every burst becomes a complete (`X`) event on the track of the cpu it ran on, and instant
+/// (`i`) events mark arrivals, preemptions, blocks on i/o and completions.
+///
+/// the viewers group tracks by pid, so each schedule is filed under its own pid.
+fn trace_events(processes: &[SimProcess], label: &str, pid: usize) -> Vec<String> {
+    let mut events = vec![format!(
+        r#"{{"name":"process_name","ph":"M","pid":{},"args":{{"name":"{}"}}}}"#,
+        pid,
+        escape_json(label)
+    )];
+    let cpus = processes
+        .iter()
+        .flat_map(|process| process.history().iter().map(|burst| burst.cpu + 1))
+        .max()
+        .unwrap_or(1);
+    for cpu in 0..cpus {
+        events.push(format!(
+            r#"{{"name":"thread_name","ph":"M","pid":{},"tid":{},"args":{{"name":"CPU {}"}}}}"#,
+            pid, cpu, cpu
+        ));
+    }
+
+    for process in processes {
+        let name = escape_json(&process.name);
+        events.push(format!(
+            r#"{{"name":"arrival {}","cat":"arrival","ph":"i","s":"p","ts":{},"pid":{}}}"#,
+            name,
+            ts(process.arrival),
+            pid
+        ));
+
+        let mut remaining = process.total_burst();
+        for burst in process.history() {
+            remaining -= burst.length;
+            events.push(format!(
+                r#"{{"name":"{}","cat":"burst","ph":"X","ts":{},"dur":{},"pid":{},"tid":{},"args":{{"priority":{},"remaining":{}}}}}"#,
+                name,
+                ts(burst.start),
+                ts(burst.length),
+                pid,
+                burst.cpu,
+                process.priority,
+                remaining
+            ));
+            let kind = match remaining {
+                0 => "completion",
+                _ if burst.blocked => "block",
+                _ => "preemption",
+            };
+            events.push(format!(
+                r#"{{"name":"{} {}","cat":"{}","ph":"i","s":"t","ts":{},"pid":{},"tid":{}}}"#,
+                kind,
+                name,
+                kind,
+                ts(burst.end()),
+                pid,
+                burst.cpu
+            ));
+        }
+    }
+    events
+}
+
+/// write finished schedules in the chrome trace event json format, which can be opened
+/// directly by chrome://tracing or the perfetto ui. each labelled schedule is shown as a
+/// separate process.
+pub fn write_chrome_trace<W: Write>(
+    writer: &mut W,
+    runs: &[(String, Vec<SimProcess>)],
+) -> io::Result<()> {
+    writeln!(writer, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+    let events: Vec<String> = runs
+        .iter()
+        .enumerate()
+        .flat_map(|(index, (label, processes))| trace_events(processes, label, index + 1))
+        .collect();
+    for (index, event) in events.iter().enumerate() {
+        let separator = if index + 1 < events.len() { "," } else { "" };
+        writeln!(writer, "{}{}", event, separator)?;
+    }
+    writeln!(writer, "]}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo;
+    use crate::events::NullSink;
+    use crate::fixtures;
+
+    #[test]
+    fn escape_json_special_characters() {
+        assert_eq!(escape_json(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_json("a\nb\u{1}"), "a\\nb\\u0001");
+    }
+
+    #[test]
+    fn complete_event_per_burst() {
+        // A runs 0-8, B 8-12 and A again 12-16.
+        let schedule = fixtures::round_robin(&["A,1,12", "B,1,4"], 8);
+        let events = trace_events(&schedule, "RR", 1);
+        let complete = events.iter().filter(|e| e.contains(r#""ph":"X""#)).count();
+        assert_eq!(complete, 3);
+    }
+
+    #[test]
+    fn instant_events_for_preemption_and_completion() {
+        let schedule = fixtures::round_robin(&["A,1,12", "B,1,4"], 8);
+        let events = trace_events(&schedule, "RR", 1);
+        let count = |needle: &str| events.iter().filter(|e| e.contains(needle)).count();
+        assert_eq!(count(r#""cat":"arrival""#), 2);
+        assert_eq!(count(r#""cat":"preemption""#), 1);
+        assert_eq!(count(r#""cat":"completion""#), 2);
+        assert!(events.iter().any(|e| e
+            .contains(r#""name":"preemption A","cat":"preemption","ph":"i","s":"t","ts":8000"#)));
+    }
+
+    #[test]
+    fn bursts_ending_in_io_are_blocks() {
+        // A runs 0-3, blocks for 5 while B runs 3-7, then finishes 8-10.
+        let workload = vec![
+            SimProcess::try_from("A,1,3").unwrap().with_io(5, 2),
+            SimProcess::try_from("B,1,4").unwrap(),
+        ];
+        let schedule = algo::round_robin(workload, 10, &mut NullSink);
+        let events = trace_events(&schedule, "RR", 1);
+        let count = |needle: &str| events.iter().filter(|e| e.contains(needle)).count();
+        assert_eq!(count(r#""cat":"preemption""#), 0);
+        assert_eq!(count(r#""cat":"block""#), 1);
+        assert!(events
+            .iter()
+            .any(|e| e.contains(r#""name":"block A","cat":"block","ph":"i","s":"t","ts":3000"#)));
+    }
+
+    #[test]
+    fn run_to_completion_is_never_preempted() {
+        let events = trace_events(&fixtures::fcfs(&["A,1,12", "B,1,4"]), "FCFS", 1);
+        assert!(!events.iter().any(|e| e.contains(r#""cat":"preemption""#)));
+    }
+
+    #[test]
+    fn written_document_is_bracketed() {
+        let mut buffer: Vec<u8> = vec![];
+        let runs = vec![(String::from("FCFS"), fixtures::fcfs(&["A,1,3"]))];
+        write_chrome_trace(&mut buffer, &runs).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        assert!(document.starts_with("{\"displayTimeUnit\":\"ms\",\"traceEvents\":["));
+        assert!(document.trim_end().ends_with("]}"));
+        assert!(!document.contains(",\n]"));
+    }
+
+    #[test]
+    fn each_run_has_its_own_pid() {
+        let lines = ["A,1,3", "B,1,4"];
+        let mut buffer: Vec<u8> = vec![];
+        let runs = vec![
+            (String::from("RR"), fixtures::round_robin(&lines, 2)),
+            (String::from("FCFS"), fixtures::fcfs(&lines)),
+        ];
+        write_chrome_trace(&mut buffer, &runs).unwrap();
+        let document = String::from_utf8(buffer).unwrap();
+        assert!(document.contains(r#""pid":1,"args":{"name":"RR"}"#));
+        assert!(document.contains(r#""pid":2,"args":{"name":"FCFS"}"#));
+    }
+}
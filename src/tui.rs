@@ -0,0 +1,376 @@
+use crate::events::{EventSink, MemorySink};
+use crate::metrics;
+use crate::sim::{ProcessId, ProcessState, SimProcess, Simulation};
+use crate::{Parameters, Result, ScheduleKind};
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+const CLEAR_LINE: &str = "\x1b[K";
+const RESET: &str = "\x1b[0m";
+const MAX_SPEED: u32 = 64;
+/// how many of the most recent events stay on screen.
+const RECENT_EVENTS: usize = 6;
+const KEYS: &str =
+    "keys: space play/pause | n step | +/- speed | a next algorithm | r restart | q quit";
+
+/// the state of an interactive session: the workload, the simulation being watched and how fast
+/// it is being played back.
+pub struct Tui {
+    workload: Vec<SimProcess>,
+    kind: ScheduleKind,
+    parameters: Parameters,
+    simulation: Simulation,
+    log: MemorySink,
+    /// the simulated time on screen, which runs ahead of the clock between events.
+    now: u32,
+    playing: bool,
+    /// simulated ticks played per frame.
+    speed: u32,
+}
+
+impl Tui {
+    pub fn new(workload: Vec<SimProcess>, kind: ScheduleKind, parameters: Parameters) -> Self {
+        let simulation = kind.simulation(workload.clone(), &parameters);
+        Self {
+            workload,
+            kind,
+            parameters,
+            simulation,
+            log: MemorySink::default(),
+            now: 0,
+            playing: false,
+            speed: 1,
+        }
+    }
+
+    pub fn kind(&self) -> ScheduleKind {
+        self.kind
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    /// start the workload over from time zero, keeping the playback speed.
+    pub fn restart(&mut self) {
+        self.simulation = self
+            .kind
+            .simulation(self.workload.clone(), &self.parameters);
+        self.log = MemorySink::default();
+        self.now = 0;
+    }
+
+    /// advance playback by one frame.
+    pub fn tick(&mut self) {
+        if !self.playing {
+            return;
+        }
+        self.now += self.speed;
+        self.simulation.run_until(self.now, &mut self.log);
+        if self.simulation.is_finished() {
+            self.playing = false;
+            self.now = self.simulation.clock();
+        }
+    }
+
+    /// process the next scheduling event, pausing playback.
+    pub fn step(&mut self) {
+        self.playing = false;
+        if let Some(log) = self.simulation.step() {
+            log.into_iter().for_each(|event| self.log.record(event));
+            self.now = self.simulation.clock();
+        }
+    }
+
+    /// react to a key press, returning false when the session should end.
+    pub fn handle_key(&mut self, key: u8) -> bool {
+        match key {
+            b'q' | b'Q' | 0x03 => return false,
+            b' ' => self.playing = !self.playing && !self.simulation.is_finished(),
+            b'n' | b'.' => self.step(),
+            b'+' | b'=' => self.speed = (self.speed * 2).min(MAX_SPEED),
+            b'-' | b'_' => self.speed = (self.speed / 2).max(1),
+            b'a' | b'\t' => {
+                let index = ScheduleKind::ALL
+                    .iter()
+                    .position(|kind| *kind == self.kind)
+                    .unwrap_or(0);
+                self.kind = ScheduleKind::ALL[(index + 1) % ScheduleKind::ALL.len()];
+                self.restart();
+            }
+            b'r' => self.restart(),
+            _ => (),
+        }
+        true
+    }
+
+    fn names(&self, ids: impl Iterator<Item = ProcessId>) -> String {
+        let processes = self.simulation.processes();
+        ids.map(|id| format!("{}{}{}", color(id), processes[id].name, RESET))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn in_state(&self, state: ProcessState) -> impl Iterator<Item = ProcessId> + '_ {
+        (0..self.simulation.processes().len()).filter(move |id| self.simulation.state(*id) == state)
+    }
+
+    /// the process on a cpu at a given tick, if any.
+    fn running_at(&self, cpu: usize, tick: u32) -> Option<ProcessId> {
+        if let (Some(id), Some(since)) = (
+            self.simulation.running_on(cpu),
+            self.simulation.running_since(cpu),
+        ) {
+            if since <= tick {
+                return Some(id);
+            }
+        }
+        self.simulation.processes().iter().position(|process| {
+            process
+                .history()
+                .iter()
+                .any(|burst| burst.cpu == cpu && burst.start <= tick && tick < burst.end())
+        })
+    }
+
+    /// the gantt strip of a cpu for the most recent ticks, one column per tick.
+    fn gantt(&self, cpu: usize, columns: usize) -> String {
+        let end = self.now;
+        let start = end.saturating_sub(columns as u32);
+        let mut strip = String::new();
+        for tick in start..end {
+            match self.running_at(cpu, tick) {
+                Some(id) => strip.push_str(&format!("{}{}{}", color(id), symbol(id), RESET)),
+                None => strip.push('.'),
+            }
+        }
+        format!(
+            "{:<8}|{}| {}..{}",
+            format!("CPU {}", cpu),
+            strip,
+            start,
+            end
+        )
+    }
+
+    fn metrics(&self) -> String {
+        let processes = self.simulation.processes();
+        let finished: Vec<SimProcess> = processes
+            .iter()
+            .filter(|process| process.is_finished())
+            .cloned()
+            .collect();
+        let summary = metrics::summarize(&finished);
+        format!(
+            "finished {}/{} | avg wait {:.2} | avg turnaround {:.2} | avg response {:.2} | switches {}",
+            finished.len(),
+            processes.len(),
+            summary.average_wait,
+            summary.average_turnaround,
+            summary.average_response,
+            metrics::context_switches(processes)
+        )
+    }
+
+    /// draw one full frame for a terminal of the given width.
+    pub fn render(&self, width: usize) -> String {
+        let status = match (self.playing, self.simulation.is_finished()) {
+            (_, true) => String::from("finished"),
+            (true, false) => format!("playing x{}", self.speed),
+            (false, false) => format!("paused x{}", self.speed),
+        };
+        let legend = (0..self.simulation.processes().len())
+            .map(|id| {
+                format!(
+                    "{}{}{} {}",
+                    color(id),
+                    symbol(id),
+                    RESET,
+                    self.simulation.processes()[id].name
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("  ");
+
+        let mut lines = vec![
+            format!(
+                "scheduler tui | {:?} (quantum {}) | time {:06} | {}",
+                self.kind, self.parameters.quantum, self.now, status
+            ),
+            String::new(),
+        ];
+        for cpu in 0..self.simulation.cpus() {
+            lines.push(format!(
+                "{:<8}| {}",
+                format!("CPU {}", cpu),
+                self.names(self.simulation.running_on(cpu).into_iter())
+            ));
+        }
+        lines.extend([
+            format!(
+                "ready   | {}",
+                self.names(self.simulation.ready_queue().into_iter())
+            ),
+            format!(
+                "blocked | {}",
+                self.names(self.in_state(ProcessState::Blocked))
+            ),
+            format!(
+                "done    | {}",
+                self.names(self.in_state(ProcessState::Finished))
+            ),
+            String::new(),
+        ]);
+        let columns = width.saturating_sub(24).max(10);
+        lines.extend((0..self.simulation.cpus()).map(|cpu| self.gantt(cpu, columns)));
+        lines.extend([legend, String::new(), self.metrics(), String::new()]);
+        let skip = self.log.events.len().saturating_sub(RECENT_EVENTS);
+        lines.extend(
+            self.log.events[skip..]
+                .iter()
+                .map(|event| event.to_string()),
+        );
+        lines.resize(
+            lines.len() + RECENT_EVENTS - (self.log.events.len() - skip),
+            String::new(),
+        );
+        lines.push(String::new());
+        lines.push(String::from(KEYS));
+
+        let mut frame = String::from("\x1b[H");
+        for line in lines {
+            frame.push_str(&line);
+            frame.push_str(CLEAR_LINE);
+            frame.push('\n');
+        }
+        frame.push_str("\x1b[J");
+        frame
+    }
+}
+
+/// a one character label for a process on the gantt strip.
+fn symbol(id: ProcessId) -> char {
+    const SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    SYMBOLS[id % SYMBOLS.len()] as char
+}
+
+fn color(id: ProcessId) -> String {
+    format!("\x1b[{}m", 31 + id % 6)
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(io::Error::other("the tui needs an interactive terminal")),
+    }
+}
+
+/// puts the terminal into unbuffered, silent input mode until dropped.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        // reads return after at most a tenth of a second, which paces the animation.
+        stty(&["-icanon", "-echo", "min", "0", "time", "1"])?;
+        print!("\x1b[?25l\x1b[2J");
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// the terminal width in columns, falling back to 80 when it cannot be determined.
+fn terminal_width() -> usize {
+    stty(&["size"])
+        .ok()
+        .and_then(|size| size.split_whitespace().nth(1)?.parse().ok())
+        .unwrap_or(80)
+}
+
+/// animate a workload in the terminal until the user quits.
+pub fn run(workload: Vec<SimProcess>, kind: ScheduleKind, parameters: Parameters) -> Result<()> {
+    let mut tui = Tui::new(workload, kind, parameters);
+    let width = terminal_width();
+    let _raw = RawMode::enable()?;
+    let mut stdin = io::stdin();
+    let mut keys = [0u8; 16];
+    loop {
+        tui.tick();
+        print!("{}", tui.render(width));
+        io::stdout().flush()?;
+        let count = stdin.read(&mut keys)?;
+        if !keys[..count].iter().all(|key| tui.handle_key(*key)) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn build_tui() -> Tui {
+        let workload = fixtures::workload(&["T1,1,15", "T2,1,5"]);
+        Tui::new(workload, ScheduleKind::RR, Parameters::default())
+    }
+
+    #[test]
+    fn play_advances_by_speed() {
+        let mut tui = build_tui();
+        tui.handle_key(b'+');
+        tui.handle_key(b' ');
+        tui.tick();
+        tui.tick();
+        assert_eq!(tui.now, 4);
+        assert_eq!(tui.simulation().running(), vec![0]);
+        for _ in 0..10 {
+            tui.tick();
+        }
+        assert!(tui.simulation().is_finished());
+        assert!(!tui.playing);
+        assert_eq!(tui.now, 20);
+    }
+
+    #[test]
+    fn switching_algorithm_restarts_workload() {
+        let mut tui = build_tui();
+        tui.step();
+        tui.step();
+        assert_eq!(tui.now, 10);
+        tui.handle_key(b'a');
+        assert_eq!(tui.kind(), ScheduleKind::PriorityRR);
+        assert_eq!(tui.now, 0);
+        tui.handle_key(b'a');
+        assert_eq!(tui.kind(), ScheduleKind::Fair);
+        tui.handle_key(b'a');
+        assert_eq!(tui.kind(), ScheduleKind::FCFS);
+        assert!(!tui.handle_key(b'q'));
+    }
+
+    #[test]
+    fn render_shows_cpu_queue_and_gantt() {
+        let mut tui = build_tui();
+        tui.step();
+        tui.step();
+        let frame = tui.render(80);
+        assert!(frame.contains("time 000010"));
+        assert!(frame.contains(&format!("CPU 0   | {}T2{}", color(1), RESET)));
+        assert!(frame.contains(&format!("ready   | {}T1{}", color(0), RESET)));
+        let strip = format!("{}A{}", color(0), RESET).repeat(10);
+        assert!(frame.contains(&format!("|{}| 0..10", strip)));
+    }
+}
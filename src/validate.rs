@@ -0,0 +1,247 @@
+//! checking a workload for every mistake at once.
+//!
+//! reading a workload stops at the first line it cannot parse. validation carries on and also
+//! reports lines that parse but are probably wrong, so a whole file can be fixed in one go.
+
+use crate::diagnostic::Diagnostic;
+use crate::document::{self, InputFormat};
+use crate::sim::SimProcess;
+use crate::workload::{self, Layout};
+use crate::{ProgramError, Result};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// the documented range of priorities.
+pub const PRIORITIES: std::ops::RangeInclusive<u8> = 1..=10;
+
+/// point at field `name` of a line, or at the whole line if it does not have one.
+fn field(layout: &Layout, line: &str, name: &str, message: String) -> Diagnostic {
+    let fields = workload::split_fields(line);
+    match layout.column(name).and_then(|index| fields.get(index)) {
+        Some(field) => Diagnostic::new(line, field.column, field.width, message).with_field(name),
+        None => Diagnostic::line(line, message),
+    }
+}
+
+/// the checks every process of a workload goes through, in order, whatever format it was read
+/// from. problems are reported against `place`, such as `on line 3`.
+#[derive(Default)]
+struct Checks {
+    /// where each name was first used.
+    names: HashMap<String, String>,
+    last_arrival: Option<(u32, String)>,
+}
+
+impl Checks {
+    /// the problems with the next process, each as the field at fault and a message.
+    fn check(&mut self, process: &SimProcess, place: String) -> Vec<(&'static str, String)> {
+        let mut problems = vec![];
+        match self.names.get(&process.name) {
+            Some(first) => problems.push((
+                "name",
+                format!("duplicate name '{}', first used {}", process.name, first),
+            )),
+            None => {
+                self.names.insert(process.name.clone(), place.clone());
+            }
+        }
+        if !PRIORITIES.contains(&process.priority) {
+            let message = format!(
+                "priority {} is outside {} to {}",
+                process.priority,
+                PRIORITIES.start(),
+                PRIORITIES.end()
+            );
+            problems.push(("priority", message));
+        }
+        let cpu: Vec<u32> = process.bursts().into_iter().step_by(2).collect();
+        for (phase, burst) in cpu.iter().enumerate() {
+            if *burst == 0 {
+                let message = match cpu.len() {
+                    1 => String::from("burst must be at least 1"),
+                    count => format!("cpu burst {} of {} must be at least 1", phase + 1, count),
+                };
+                problems.push(("burst", message));
+            }
+        }
+        if let Some((previous, previous_place)) = &self.last_arrival {
+            if process.arrival < *previous {
+                let message = format!(
+                    "arrival {} is earlier than arrival {} {}",
+                    process.arrival, previous, previous_place
+                );
+                problems.push(("arrival", message));
+            }
+        }
+        self.last_arrival = Some((process.arrival, place));
+        problems
+    }
+}
+
+/// every problem in a workload: lines that do not parse, duplicate names, priorities outside
+/// 1 to 10, cpu bursts of zero and arrivals earlier than the line before.
+pub fn validate<R: BufRead>(source: &str, reader: R) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = vec![];
+    let mut checks = Checks::default();
+    let (layout, lines) = match workload::read_lines(source, reader) {
+        Ok(read) => read,
+        // without a usable header none of the lines can be read.
+        Err(ProgramError::InvalidWorkload(diagnostic)) => return Ok(vec![diagnostic]),
+        Err(e) => return Err(e),
+    };
+    for workload::Line {
+        number,
+        text: line,
+        process,
+    } in lines
+    {
+        let process = match process {
+            Ok(process) => process,
+            Err(ProgramError::InvalidWorkload(diagnostic)) => {
+                diagnostics.push(diagnostic);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for (name, message) in checks.check(&process, format!("on line {}", number)) {
+            diagnostics.push(field(&layout, &line, name, message).at(source, number));
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// every problem with processes read from a json or toml document. documents have no lines to
+/// point at, so each problem names the task it was found in.
+pub fn validate_processes(source: &str, processes: &[SimProcess]) -> Vec<Diagnostic> {
+    let mut checks = Checks::default();
+    let mut diagnostics = vec![];
+    for (index, process) in processes.iter().enumerate() {
+        let task = format!("task {} ('{}')", index + 1, process.name);
+        for (name, message) in checks.check(process, format!("in {}", task)) {
+            diagnostics.push(
+                Diagnostic::line(&task, message)
+                    .with_field(name)
+                    .at(source, 0),
+            );
+        }
+    }
+    diagnostics
+}
+
+/// validate the workload file or preset named by `source`, read in `format` or the format its
+/// extension names. json and toml documents that cannot be read at all are returned as an error.
+pub fn validate_workload(source: &str, format: Option<InputFormat>) -> Result<Vec<Diagnostic>> {
+    let reader = crate::open_workload(source)?;
+    let source = crate::source_name(source);
+    match format.unwrap_or_else(|| InputFormat::detect(source)) {
+        InputFormat::Csv => validate(source, reader),
+        format => {
+            let processes = document::read(source, reader, format)?;
+            Ok(validate_processes(source, &processes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_every_problem() {
+        let text = "\
+T1, 4, 20, 5
+T2, x, 45
+T1, 2, 10, 7
+T3, 11, 0, 9
+T4, 3, 15, 2
+T5, 3, 15
+";
+        let diagnostics = validate("list.txt", text.as_bytes()).unwrap();
+        let found: Vec<(usize, Option<&str>)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.field.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, Some("priority")),
+                (3, Some("name")),
+                (4, Some("priority")),
+                (4, Some("burst")),
+                (5, Some("arrival")),
+                (6, None),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "duplicate name 'T1', first used on line 1"
+        );
+        assert_eq!(
+            diagnostics[4].message,
+            "arrival 2 is earlier than arrival 9 on line 4"
+        );
+    }
+
+    #[test]
+    fn report_zero_cpu_phases() {
+        let text = "name, priority, bursts\nT1, 4, 0 5 10\nT2, 4, 10 5 0\nT3, 4, 10 0 10\n";
+        let diagnostics = validate("list.txt", text.as_bytes()).unwrap();
+        let found: Vec<(usize, &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, "cpu burst 1 of 2 must be at least 1"),
+                (3, "cpu burst 2 of 2 must be at least 1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn report_problems_in_documents() -> Result<()> {
+        let text = r#"[
+    {"name": "a", "priority": 4, "bursts": 20, "arrival": 5},
+    {"name": "a", "priority": 11, "bursts": [10, 5, 0], "arrival": 2}
+]"#;
+        let processes = document::read("list.json", text.as_bytes(), InputFormat::Json)?;
+        let diagnostics = validate_processes("list.json", &processes);
+        let found: Vec<(&str, Option<&str>)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message.as_str(), diagnostic.field.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "duplicate name 'a', first used in task 1 ('a')",
+                    Some("name")
+                ),
+                ("priority 11 is outside 1 to 10", Some("priority")),
+                ("cpu burst 2 of 2 must be at least 1", Some("burst")),
+                (
+                    "arrival 2 is earlier than arrival 5 in task 1 ('a')",
+                    Some("arrival")
+                ),
+            ]
+        );
+        assert!(diagnostics.iter().all(|d| d.text == "task 2 ('a')"));
+        Ok(())
+    }
+
+    #[test]
+    fn presets_and_the_sample_workload_are_clean() {
+        assert!(validate_workload(crate::DEFAULT_PROCESS_FILENAME, None)
+            .unwrap()
+            .is_empty());
+        for preset in &crate::preset::PRESETS {
+            let source = format!("{}{}", crate::preset::PREFIX, preset.name);
+            assert!(
+                validate_workload(&source, None).unwrap().is_empty(),
+                "{}",
+                source
+            );
+        }
+    }
+}
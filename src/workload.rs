@@ -0,0 +1,445 @@
+//! the comma-separated workload format.
+//!
+//! each process is a line of `name, priority, burst[, arrival]`. blank lines are skipped and `#`
+//! starts a comment that runs to the end of the line. names holding commas or `#` are quoted,
+//! with `""` standing for a quote inside them.
+//!
+//! the first line may instead be a header naming the columns, after which they can come in any
+//! order and columns the simulator does not know are ignored. a header can also name the
+//! optional `deadline`, `group`, `tickets`, `processors` and `affinity` columns, whose cells may
+//! be empty:
+//!
+//! ```text
+//! # nightly jobs
+//! arrival, name, owner, priority, burst
+//! 0, "backup, full", ops, 8, 120
+//! ```
+//!
+//! a `bursts` column can stand in for `burst` to give processes i/o waits. its cells alternate
+//! cpu and i/o time separated by spaces, starting and ending with cpu time: `20 5 10`. an
+//! `affinity` cell likewise lists the cpus a process may run on: `0 2`.
+
+use crate::diagnostic::Diagnostic;
+use crate::sim::SimProcess;
+use crate::{ProgramError, Result};
+use std::fmt::Display;
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// one comma-separated field of a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// the character the field starts at, including any opening quote.
+    pub column: usize,
+    /// how many characters the field spans in the line.
+    pub width: usize,
+    /// the field without surrounding whitespace and quotes.
+    pub text: String,
+}
+
+/// split a line at the commas outside quotes, stopping at a comment.
+pub fn split_fields(line: &str) -> Vec<Field> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut fields = vec![];
+    let mut index = 0;
+    loop {
+        while index < chars.len() && chars[index].is_whitespace() {
+            index += 1;
+        }
+        let column = index;
+        let mut text = String::new();
+        if chars.get(index) == Some(&'"') {
+            index += 1;
+            while index < chars.len() {
+                index += 1;
+                match (chars[index - 1], chars.get(index)) {
+                    ('"', Some('"')) => {
+                        text.push('"');
+                        index += 1;
+                    }
+                    ('"', _) => break,
+                    (c, _) => text.push(c),
+                }
+            }
+        }
+        // text after a closing quote is kept, so that it shows up in the field.
+        let rest = index;
+        while index < chars.len() && chars[index] != ',' && chars[index] != '#' {
+            index += 1;
+        }
+        let rest: String = chars[rest..index].iter().collect();
+        text.push_str(rest.trim_end());
+        let width = index - column - (rest.chars().count() - rest.trim_end().chars().count());
+        fields.push(Field {
+            column,
+            width,
+            text,
+        });
+        match chars.get(index) {
+            Some(',') => index += 1,
+            _ => break,
+        }
+    }
+    fields
+}
+
+/// whether a line holds nothing but whitespace and perhaps a comment.
+pub fn is_blank(line: &str) -> bool {
+    let fields = split_fields(line);
+    fields.len() == 1 && fields[0].width == 0
+}
+
+/// quote a name if it would not read back as itself.
+pub fn quote(name: &str) -> String {
+    let plain = !name.is_empty()
+        && name.trim() == name
+        && !name.starts_with('"')
+        && !name.contains([',', '#']);
+    match plain {
+        true => String::from(name),
+        false => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+fn parse_field<T>(line: &str, field: &Field, name: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    field.text.parse::<T>().map_err(|e| {
+        let message = format!("invalid {} '{}': {}", name, field.text, e);
+        let diagnostic = Diagnostic::new(line, field.column, field.width, message);
+        ProgramError::InvalidWorkload(diagnostic.with_field(name))
+    })
+}
+
+/// read a cell of a `bursts` column: cpu and i/o times separated by spaces, alternating and
+/// starting and ending with cpu time.
+fn parse_bursts(line: &str, field: &Field) -> Result<Vec<u32>> {
+    let invalid = |message: String| {
+        let diagnostic = Diagnostic::new(line, field.column, field.width, message);
+        ProgramError::InvalidWorkload(diagnostic.with_field("burst"))
+    };
+    let bursts = field
+        .text
+        .split_whitespace()
+        .map(|text| {
+            text.parse::<u32>()
+                .map_err(|e| invalid(format!("invalid burst '{}': {}", text, e)))
+        })
+        .collect::<Result<Vec<u32>>>()?;
+    match bursts.len() % 2 {
+        1 => Ok(bursts),
+        _ => Err(invalid(String::from(
+            "bursts should alternate cpu and i/o time, starting and ending with cpu time",
+        ))),
+    }
+}
+
+/// parse an affinity cell, the cpus a process may run on separated by spaces.
+fn parse_affinity(line: &str, field: &Field) -> Result<Vec<usize>> {
+    field
+        .text
+        .split_whitespace()
+        .map(|text| {
+            text.parse::<usize>().map_err(|e| {
+                let message = format!("invalid cpu '{}': {}", text, e);
+                let diagnostic = Diagnostic::new(line, field.column, field.width, message);
+                ProgramError::InvalidWorkload(diagnostic.with_field("affinity"))
+            })
+        })
+        .collect()
+}
+
+/// the columns the workload fields are found in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    name: usize,
+    priority: usize,
+    burst: usize,
+    /// whether the burst column is a `bursts` column, alternating cpu and i/o time.
+    phases: bool,
+    arrival: Option<usize>,
+    deadline: Option<usize>,
+    group: Option<usize>,
+    tickets: Option<usize>,
+    processors: Option<usize>,
+    affinity: Option<usize>,
+    /// how many fields every line has, when a header fixed it.
+    width: Option<usize>,
+}
+
+impl Layout {
+    /// `name, priority, burst[, arrival]`, used when there is no header.
+    pub const POSITIONAL: Layout = Layout {
+        name: 0,
+        priority: 1,
+        burst: 2,
+        phases: false,
+        arrival: Some(3),
+        deadline: None,
+        group: None,
+        tickets: None,
+        processors: None,
+        affinity: None,
+        width: None,
+    };
+
+    /// read a header line, if `line` is one: a line with a `name` column.
+    pub fn header(line: &str) -> Option<Result<Layout>> {
+        let fields = split_fields(line);
+        let find = |name: &str| {
+            fields
+                .iter()
+                .position(|field| field.text.eq_ignore_ascii_case(name))
+        };
+        let name = find("name")?;
+        let invalid = |message| {
+            Some(Err(ProgramError::InvalidWorkload(Diagnostic::line(
+                line, message,
+            ))))
+        };
+        for (index, field) in fields.iter().enumerate() {
+            let repeated = fields[..index]
+                .iter()
+                .any(|other| other.text.eq_ignore_ascii_case(&field.text));
+            if repeated {
+                let message = format!("column '{}' appears twice in the header", field.text);
+                let diagnostic = Diagnostic::new(line, field.column, field.width, message);
+                return Some(Err(ProgramError::InvalidWorkload(diagnostic)));
+            }
+        }
+        let priority = match find("priority") {
+            Some(priority) => priority,
+            None => return invalid(String::from("the header has no 'priority' column")),
+        };
+        let (burst, phases) = match (find("burst"), find("bursts")) {
+            (Some(burst), None) => (burst, false),
+            (None, Some(bursts)) => (bursts, true),
+            (Some(_), Some(_)) => {
+                return invalid(String::from(
+                    "the header has both a 'burst' and a 'bursts' column",
+                ))
+            }
+            (None, None) => return invalid(String::from("the header has no 'burst' column")),
+        };
+        Some(Ok(Layout {
+            name,
+            priority,
+            burst,
+            phases,
+            arrival: find("arrival"),
+            deadline: find("deadline"),
+            group: find("group"),
+            tickets: find("tickets"),
+            processors: find("processors"),
+            affinity: find("affinity"),
+            width: Some(fields.len()),
+        }))
+    }
+
+    /// the position of a field on each line, if lines have it.
+    pub fn column(&self, field: &str) -> Option<usize> {
+        match field {
+            "name" => Some(self.name),
+            "priority" => Some(self.priority),
+            "burst" => Some(self.burst),
+            "arrival" => self.arrival,
+            "deadline" => self.deadline,
+            "group" => self.group,
+            "tickets" => self.tickets,
+            "processors" => self.processors,
+            "affinity" => self.affinity,
+            _ => None,
+        }
+    }
+
+    /// parse one line of a workload laid out like this.
+    pub fn parse(&self, line: &str) -> Result<SimProcess> {
+        let fields = split_fields(line);
+        let expected = match self.width {
+            None if (3..=4).contains(&fields.len()) => None,
+            None => Some(String::from("'name, priority, burst[, arrival]'")),
+            Some(width) if width == fields.len() => None,
+            Some(width) => Some(format!("{} fields as in the header", width)),
+        };
+        if let Some(expected) = expected {
+            let message = format!(
+                "expected {} but found {} field{}",
+                expected,
+                fields.len(),
+                if fields.len() == 1 { "" } else { "s" }
+            );
+            return Err(ProgramError::InvalidWorkload(Diagnostic::line(
+                line, message,
+            )));
+        }
+        let name = &fields[self.name];
+        if name.text.is_empty() {
+            let diagnostic =
+                Diagnostic::new(line, name.column, name.width, String::from("missing name"));
+            return Err(ProgramError::InvalidWorkload(diagnostic.with_field("name")));
+        }
+        let priority = parse_field(line, &fields[self.priority], "priority")?;
+        let bursts = match self.phases {
+            true => parse_bursts(line, &fields[self.burst])?,
+            false => vec![parse_field(line, &fields[self.burst], "burst")?],
+        };
+        let arrival = match self.arrival.and_then(|index| fields.get(index)) {
+            Some(field) => parse_field(line, field, "arrival")?,
+            None => 0,
+        };
+        let mut process =
+            SimProcess::new(name.text.clone(), priority, bursts[0]).with_arrival(arrival);
+        for pair in bursts[1..].chunks(2) {
+            process = process.with_io(pair[0], pair[1]);
+        }
+
+        // attributes only a header can name, which may be left empty.
+        let optional = |field: &str| {
+            self.column(field)
+                .and_then(|index| fields.get(index))
+                .filter(|field| !field.text.is_empty())
+        };
+        if let Some(field) = optional("deadline") {
+            process.deadline = Some(parse_field(line, field, "deadline")?);
+        }
+        process.group = optional("group").map(|field| field.text.clone());
+        if let Some(field) = optional("tickets") {
+            process.tickets = Some(parse_field(line, field, "tickets")?);
+        }
+        if let Some(field) = optional("processors") {
+            process.processors = Some(parse_field(line, field, "processors")?);
+        }
+        if let Some(field) = optional("affinity") {
+            process.affinity = parse_affinity(line, field)?;
+        }
+        Ok(process)
+    }
+}
+
+/// a line of a workload that describes a process.
+pub struct Line {
+    /// the line number, starting at 1.
+    pub number: usize,
+    pub text: String,
+    /// the process, or why the line does not parse.
+    pub process: Result<SimProcess>,
+}
+
+/// read every process line of a workload, skipping blank lines, comments and a header. errors
+/// note `source` and the line number.
+pub fn read_lines<R: BufRead>(source: &str, reader: R) -> Result<(Layout, Vec<Line>)> {
+    let mut layout = None;
+    let mut lines = vec![];
+    for (index, text) in reader.lines().enumerate() {
+        let text = text?;
+        let number = index + 1;
+        let locate = |e| match e {
+            ProgramError::InvalidWorkload(diagnostic) => {
+                ProgramError::InvalidWorkload(diagnostic.at(source, number))
+            }
+            e => e,
+        };
+        if is_blank(&text) {
+            continue;
+        }
+        let current = match layout.take() {
+            Some(current) => current,
+            None => match Layout::header(&text) {
+                Some(header) => {
+                    layout = Some(header.map_err(locate)?);
+                    continue;
+                }
+                None => Layout::POSITIONAL,
+            },
+        };
+        let process = current.parse(&text).map_err(locate);
+        layout = Some(current);
+        lines.push(Line {
+            number,
+            text,
+            process,
+        });
+    }
+    Ok((layout.unwrap_or(Layout::POSITIONAL), lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(line: &str) -> Vec<String> {
+        split_fields(line)
+            .into_iter()
+            .map(|field| field.text)
+            .collect()
+    }
+
+    #[test]
+    fn split_quotes_and_comments() {
+        assert_eq!(texts(" T1 , 4,20 # note"), vec!["T1", "4", "20"]);
+        assert_eq!(
+            texts("\"backup, \"\"full\"\"\", 8, 120"),
+            vec!["backup, \"full\"", "8", "120"]
+        );
+        assert_eq!(texts("\"#1\", 2, 3"), vec!["#1", "2", "3"]);
+        let fields = split_fields("T2, 4x5 , 1");
+        assert_eq!((fields[1].column, fields[1].width), (4, 3));
+        assert!(is_blank("   # only a comment"));
+        assert!(is_blank(""));
+        assert!(!is_blank("\"\""));
+        for name in ["T1", "a, b", "#1", " padded", "say \"hi\""] {
+            assert_eq!(texts(&format!("{}, 1, 2", quote(name)))[0], name);
+        }
+    }
+
+    #[test]
+    fn header_maps_columns_by_name() -> Result<()> {
+        let text = "\
+# nightly jobs
+
+arrival, Name, owner, priority, burst
+0, \"backup, full\", ops, 8, 120   # weekly
+5, report, finance, 3, 40
+";
+        let (layout, lines) = read_lines("jobs.csv", text.as_bytes())?;
+        assert_eq!(layout.column("burst"), Some(4));
+        assert_eq!(lines[0].number, 4);
+        let processes = lines
+            .into_iter()
+            .map(|line| line.process)
+            .collect::<Result<Vec<SimProcess>>>()?;
+        assert_eq!(processes[0].name, "backup, full");
+        assert_eq!(processes[0].total_burst(), 120);
+        assert_eq!((processes[1].priority, processes[1].arrival), (3, 5));
+
+        let (_, lines) = read_lines("jobs.csv", "name, burst, priority\nT1, 5\n".as_bytes())?;
+        match &lines[0].process {
+            Err(ProgramError::InvalidWorkload(diagnostic)) => {
+                assert_eq!(diagnostic.line, 2);
+                assert_eq!(
+                    diagnostic.message,
+                    "expected 3 fields as in the header but found 2 fields"
+                );
+            }
+            val => panic!("received unexpected value: {:?}", val),
+        }
+        let (_, lines) = read_lines(
+            "jobs.csv",
+            "name, priority, bursts\nT1, 2, 20 5 10\n".as_bytes(),
+        )?;
+        let process = lines[0].process.as_ref().unwrap();
+        assert_eq!(process.bursts(), vec![20, 5, 10]);
+        match read_lines("jobs.csv", "name, priority\n".as_bytes()) {
+            Err(ProgramError::InvalidWorkload(diagnostic)) => {
+                assert_eq!(diagnostic.message, "the header has no 'burst' column")
+            }
+            val => panic!(
+                "received unexpected value: {:?}",
+                val.map(|(layout, _)| layout)
+            ),
+        }
+        Ok(())
+    }
+}